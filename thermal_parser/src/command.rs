@@ -2,7 +2,7 @@ use crate::context::Context;
 use crate::graphics::GraphicsCommand;
 use std::rc::Rc;
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum DeviceCommand {
     BeginPrint,
     Initialize,
@@ -11,10 +11,17 @@ pub enum DeviceCommand {
     Feed(i16),
     FeedLine(i16),
     Cancel,
-    Pulse,
+    //Drawer kick-out connector pin 2 or 5
+    Pulse { pin: u8, on_ms: u16, off_ms: u16 },
     EndPrint,
     Transmit(Vec<u8>),
     MoveX(u16),
+    MoveXRelative(i16),
+    MoveY(u16),
+    MoveYRelative(i16),
+    BeginPage,
+    PrintPage,
+    EndPage,
 }
 
 impl DeviceCommand {
@@ -26,23 +33,36 @@ impl DeviceCommand {
             Self::Feed(n) => format!("Feed {} Motion Units", n),
             Self::FeedLine(n) => format!("Feed {} Lines", n),
             Self::Cancel => "Cancel".to_string(),
-            Self::Pulse => "Pulse".to_string(),
+            Self::Pulse { pin, on_ms, off_ms } => {
+                format!("Pulse Pin {} On {}ms Off {}ms", pin, on_ms, off_ms)
+            }
             Self::EndPrint => "End Print".to_string(),
             Self::BeginPrint => "Begin Print".to_string(),
             Self::Transmit(_b) => "Transmit Data Back".to_string(),
             Self::MoveX(_n) => "Move Horizontally".to_string(),
+            Self::MoveXRelative(_n) => "Move Horizontally Relative".to_string(),
+            Self::MoveY(_n) => "Move Vertically".to_string(),
+            Self::MoveYRelative(_n) => "Move Vertically Relative".to_string(),
+            Self::BeginPage => "Begin Page".to_string(),
+            Self::PrintPage => "Print Page".to_string(),
+            Self::EndPage => "End Page".to_string(),
         }
     }
 }
 
 #[derive(Clone, PartialEq)]
 pub enum CommandType {
+    //Device commands are taken before the command is applied to the context
     Control,
     Text,
     Graphics,
     Context,
+    //Applied to the context before its device commands are taken
     ContextControl,
     Subcommand,
+    //Executed as soon as it is received, even in the middle of the data of another command,
+    //device commands are taken before the command is applied to the context
+    RealTime,
     Unknown,
 }
 
@@ -93,6 +113,13 @@ impl Command {
         }
     }
 
+    //Printing a graphic empties the line buffer, graphics placed on the line
+    //like characters mark it as used again in their handler
+    pub fn apply_graphics(&self, context: &mut Context) {
+        context.line_is_empty = true;
+        self.handler.apply_context(self, context);
+    }
+
     // returns true if the byte was consumed or false if it was rejected
     pub fn push(&mut self, byte: u8) -> bool {
         let data_len = self.data.len();
@@ -5,10 +5,13 @@ pub fn new() -> CommandSet {
     let commands = vec![
         barcode::new(),
         bit_image::new(),
+        bmp_graphics::new(),
         cancel::new(),
         carriage_return::new(),
         code_2d::new(),
         default_line_spacing::new(),
+        define_bit_image::new(),
+        define_nv_bit_image::new(),
         feed_and_cut::new(),
         formfeed::new(),
         graphics::new(),
@@ -19,16 +22,26 @@ pub fn new() -> CommandSet {
         paper_end_sensor::new(),
         print_and_feed_lines::new(),
         print_and_feed::new(),
+        print_and_reverse_feed::new(),
         print_and_reverse_feed_lines::new(),
+        print_bit_image::new(),
+        print_nv_bit_image::new(),
+        print_page::new(),
         print_stop_sensor::new(),
         pulse::new(),
         raster_bit_image::new(),
+        real_time_control::new(),
+        real_time_request::new(),
+        real_time_status::new(),
         request_response_transmission::new(),
         set_absolute_print_pos::new(),
         set_alt_color::new(),
+        set_automatic_status_back::new(),
         set_barcode_height::new(),
         set_barcode_width::new(),
         set_black_white_invert::new(),
+        set_char_spacing::new(),
+        set_character_color::new(),
         set_character_size::new(),
         set_code_table::new(),
         set_double_strike::new(),
@@ -43,15 +56,23 @@ pub fn new() -> CommandSet {
         set_italic_on::new(),  //NOT part of ESCPOS
         set_italic_on_2::new(), //NOT part of ESCPOS
         set_justification::new(),
+        set_left_margin::new(),
         set_line_spacing::new(),
         set_panel_buttons::new(),
         set_peripheral_device::new(),
+        set_print_area_width::new(),
+        set_print_direction::new(),
         set_print_mode::new(),
+        set_relative_print_pos::new(),
         set_relative_vertical_print::new(),
+        set_rotation::new(),
         set_smoothing::new(),
         set_underline::new(),
         set_upside_down::new(),
         transmit_printer_id::new(),
+        transmit_status::new(),
+        user_setup::new(),
+        variable_bit_image::new(),
         set_page_mode::new(),
         set_absolute_vertical_print_position::new(),
         print_area::new(),
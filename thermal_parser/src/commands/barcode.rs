@@ -86,7 +86,9 @@ impl CommandHandler for BarcodeHandler {
                 }
             }
             BarcodeType::Code39 => {
-                if let Ok(barcode) = Code39::new(data.to_string()) {
+                //Start and stop characters are optional in the data, the encoder adds them
+                let symbols = data.trim_start_matches('*').trim_end_matches('*');
+                if let Ok(barcode) = Code39::new(symbols.to_string()) {
                     return Some(GraphicsCommand::Barcode(Barcode {
                         points: barcode.encode(),
                         text: data.to_string(),
@@ -12,6 +12,11 @@ struct Handler {
 }
 
 impl CommandHandler for Handler {
+    fn apply_context(&self, _command: &Command, context: &mut Context) {
+        //Bit images are placed on the current line like characters
+        context.line_is_empty = false;
+    }
+
     fn get_graphics(&self, command: &Command, _context: &Context) -> Option<GraphicsCommand> {
         let bytes: Vec<u8> = if self.col_encoded {
             graphics::column_to_raster(
@@ -28,6 +33,7 @@ impl CommandHandler for Handler {
             width: self.width,
             height: self.height,
             pixel_type: PixelType::MonochromeByte,
+            planes: vec![],
             stretch: self.stretch,
             advances_xy: false,
         }))
@@ -67,12 +73,16 @@ impl CommandHandler for Handler {
             //8 dot mode (m = 0, 1)
             self.height = 8;
             self.capacity = (self.width * self.height) / 8;
+            self.col_encoded = true;
         }
 
-        if m == 1 || m == 33 {
-            //Not currently sure what the purpose of this is
-            self.stretch = (2, 2);
-        }
+        //Single density prints each dot twice as wide, 8 dot modes print each dot three times as high
+        self.stretch = match m {
+            0 => (2, 3),
+            1 => (1, 3),
+            32 => (2, 1),
+            _ => (1, 1),
+        };
 
         //After this, we accept data until the capacity is met
         self.accept_data = true;
@@ -0,0 +1,66 @@
+use crate::{command::*, constants::*, context::*, graphics::*};
+
+//Largest bmp file accepted, an 8 bit image of 8192 x 2304 dots with its headers and palette
+const MAX_BMP_SIZE: usize = 8192 * 2304 + 54 + 256 * 4;
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        //m fn a kc1 kc2 b c followed by the bmp file
+        let function = *command.data.get(1).unwrap_or(&0u8);
+        let storage = match function {
+            67 => ImageRefStorage::Disc,
+            83 => ImageRefStorage::Ram,
+            _ => return,
+        };
+
+        let (Some(kc1), Some(kc2)) = (command.data.get(3), command.data.get(4)) else {
+            return;
+        };
+        let img_ref = ImageRef {
+            kc1: *kc1,
+            kc2: *kc2,
+            storage,
+        };
+        let tone = *command.data.get(5).unwrap_or(&48u8);
+        let color = *command.data.get(6).unwrap_or(&49u8);
+
+        if let Some(img) = Image::from_bmp(command.data.get(7..).unwrap_or(&[]), tone, color) {
+            context.graphics.store_graphics(img_ref, img);
+        }
+    }
+
+    //7 bytes of parameters, the length of the bmp file is read from its header,
+    //a corrupt length ends the command instead of taking the rest of the job
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        if data.len() < 13 {
+            data.push(byte);
+            return true;
+        }
+        if &data[7..9] != b"BM" {
+            return false;
+        }
+
+        let size = u32::from_le_bytes([data[9], data[10], data[11], data[12]]) as usize;
+        if size > MAX_BMP_SIZE {
+            return false;
+        }
+        if data.len() < size + 7 {
+            data.push(byte);
+            return true;
+        }
+        false
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Define Windows BMP Graphics",
+        vec![GS, 'D' as u8],
+        CommandType::Context,
+        DataType::Custom, //push is implemented in the CommandHandler for Custom types
+        Box::new(Handler {}),
+    )
+}
@@ -7,16 +7,20 @@ impl CommandHandler for Handler {
     fn get_device_command(
         &self,
         _command: &Command,
-        _context: &Context,
+        context: &Context,
     ) -> Option<Vec<DeviceCommand>> {
-        Some(vec![DeviceCommand::Cancel])
+        //Clears the page data, only enabled in page mode
+        if context.is_page_mode {
+            return Some(vec![DeviceCommand::Cancel]);
+        }
+        None
     }
 }
 
 pub fn new() -> Command {
     Command::new(
         "Cancel",
-        vec![CAN, '@' as u8],
+        vec![CAN],
         CommandType::Control,
         DataType::Empty,
         Box::new(Handler {}),
@@ -0,0 +1,45 @@
+use crate::{command::*, constants::*, context::*, graphics::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        let x = *command.data.get(0).unwrap_or(&0u8) as u32;
+        let y = *command.data.get(1).unwrap_or(&0u8) as u32;
+
+        //Replaces the previously defined bit image
+        context.graphics.download_bit_image = match x * y {
+            0 => None,
+            _ => Some(Image::from_column_bit_image(
+                command.data.get(2..).unwrap_or(&[]),
+                x * 8,
+                y * 8,
+            )),
+        };
+    }
+
+    //x and y in units of 8 dots followed by x * y * 8 bytes of column data
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        if data.len() < 2 {
+            data.push(byte);
+            return true;
+        }
+        let capacity = data[0] as usize * data[1] as usize * 8;
+        if data.len() < capacity + 2 {
+            data.push(byte);
+            return true;
+        }
+        false
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Define Downloaded Bit Image",
+        vec![GS, '*' as u8],
+        CommandType::Context,
+        DataType::Custom, //push is implemented in the CommandHandler for Custom types
+        Box::new(Handler {}),
+    )
+}
@@ -0,0 +1,58 @@
+use crate::{command::*, constants::*, context::*, graphics::*};
+
+#[derive(Clone)]
+struct Handler;
+
+//Width, height and column data of an image
+type BitImageData<'a> = (u32, u32, &'a [u8]);
+
+//Splits n followed by n times [xL xH yL yH d1...dk] into (width, height, data) of each image,
+//None until all of the headers have been received
+fn split_images(data: &[u8]) -> Option<(usize, Vec<BitImageData<'_>>)> {
+    let n = *data.first()?;
+    let mut pos = 1;
+    let mut images = vec![];
+
+    for _ in 0..n {
+        let header = data.get(pos..pos + 4)?;
+        let x = header[0] as u32 + header[1] as u32 * 256;
+        let y = header[2] as u32 + header[3] as u32 * 256;
+        let k = (x * y * 8) as usize;
+        let end = (pos + 4 + k).min(data.len());
+        images.push((x * 8, y * 8, &data[pos + 4..end]));
+        pos += 4 + k;
+    }
+    Some((pos, images))
+}
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        //All of the previously defined NV bit images are replaced
+        if let Some((_, images)) = split_images(&command.data) {
+            context.graphics.nv_bit_images = images
+                .into_iter()
+                .map(|(width, height, data)| Image::from_column_bit_image(data, width, height))
+                .collect();
+        }
+    }
+
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        match split_images(data) {
+            Some((len, _)) if data.len() >= len => false,
+            _ => {
+                data.push(byte);
+                true
+            }
+        }
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Define NV Bit Image",
+        vec![FS, 'q' as u8],
+        CommandType::Context,
+        DataType::Custom, //push is implemented in the CommandHandler for Custom types
+        Box::new(Handler {}),
+    )
+}
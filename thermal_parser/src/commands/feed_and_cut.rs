@@ -4,6 +4,10 @@ use crate::{command::*, constants::*, context::*};
 struct Handler;
 
 impl CommandHandler for Handler {
+    fn apply_context(&self, _command: &Command, context: &mut Context) {
+        context.line_is_empty = true;
+    }
+
     fn get_device_command(
         &self,
         command: &Command,
@@ -46,7 +50,7 @@ pub fn new() -> Command {
     Command::new(
         "Feed and Cut",
         vec![GS, 'V' as u8],
-        CommandType::Control,
+        CommandType::ContextControl,
         DataType::Custom, //push is implemented in the CommandHandler for Custom types
         Box::new(Handler {}),
     )
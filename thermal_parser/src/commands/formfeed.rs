@@ -4,8 +4,22 @@ use crate::{command::*, constants::*, context::*};
 struct Handler;
 
 impl CommandHandler for Handler {
-    fn get_text(&self, _command: &Command, _context: &Context) -> Option<String> {
-        Some("\x0c".to_string())
+    fn get_device_command(
+        &self,
+        _command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        //Prints the page and returns to standard mode, only enabled in page mode
+        if context.is_page_mode {
+            return Some(vec![DeviceCommand::PrintPage, DeviceCommand::EndPage]);
+        }
+        None
+    }
+    fn apply_context(&self, _command: &Command, context: &mut Context) {
+        if context.is_page_mode {
+            context.is_page_mode = false;
+            context.line_is_empty = true;
+        }
     }
 }
 
@@ -13,7 +27,7 @@ pub fn new() -> Command {
     Command::new(
         "Form Feed",
         vec![FF],
-        CommandType::Text,
+        CommandType::Control,
         DataType::Empty,
         Box::new(Handler {}),
     )
@@ -4,6 +4,10 @@ use crate::{command::*, constants::*, context::*};
 struct Handler;
 
 impl CommandHandler for Handler {
+    fn apply_context(&self, _command: &Command, context: &mut Context) {
+        context.line_is_empty = true;
+    }
+
     fn get_text(&self, _command: &Command, _context: &Context) -> Option<String> {
         Some("\n".to_string())
     }
@@ -1,10 +1,13 @@
 pub mod barcode;
 pub mod begin_print;
 pub mod bit_image;
+pub mod bmp_graphics;
 pub mod cancel;
 pub mod carriage_return;
 pub mod code_2d;
 pub mod default_line_spacing;
+pub mod define_bit_image;
+pub mod define_nv_bit_image;
 pub mod end_print;
 pub mod feed_and_cut;
 pub mod formfeed;
@@ -16,21 +19,31 @@ pub mod linefeed;
 pub mod paper_end_sensor;
 pub mod print_and_feed;
 pub mod print_and_feed_lines;
+pub mod print_and_reverse_feed;
 pub mod print_and_reverse_feed_lines;
 pub mod print_area;
+pub mod print_bit_image;
+pub mod print_nv_bit_image;
+pub mod print_page;
 pub mod print_stop_sensor;
 pub mod pulse;
 pub mod raster_bit_image;
+pub mod real_time_control;
+pub mod real_time_request;
+pub mod real_time_status;
 pub mod request_response_transmission;
 pub mod select_standard_mode;
 pub mod set_absolute_print_pos;
 pub mod set_absolute_vertical_print_position;
 pub mod set_alt_color;
+pub mod set_automatic_status_back;
 pub mod set_barcode_font;
 pub mod set_barcode_height;
 pub mod set_barcode_hri;
 pub mod set_barcode_width;
 pub mod set_black_white_invert;
+pub mod set_char_spacing;
+pub mod set_character_color;
 pub mod set_character_size;
 pub mod set_code_table;
 pub mod set_double_strike;
@@ -43,19 +56,27 @@ pub mod set_italic_off_2;
 pub mod set_italic_on;
 pub mod set_italic_on_2;
 pub mod set_justification;
+pub mod set_left_margin;
 pub mod set_line_spacing;
 pub mod set_page_mode;
 pub mod set_panel_buttons;
 pub mod set_peripheral_device;
+pub mod set_print_area_width;
+pub mod set_print_direction;
 pub mod set_print_mode;
+pub mod set_relative_print_pos;
 pub mod set_relative_vertical_print;
+pub mod set_rotation;
 pub mod set_smoothing;
 pub mod set_underline;
 pub mod set_upside_down;
 pub mod text;
 pub mod transmit_printer_id;
+pub mod transmit_status;
 pub mod unknown;
+pub mod user_setup;
+pub mod variable_bit_image;
 
 pub mod cancel_cjk_mode;
 pub mod enter_cjk_mode;
-//TODO: add enter_cjk_mode, cancel_cjk_mode, select_cjk_font, set_left_margin.
+//TODO: add select_cjk_font.
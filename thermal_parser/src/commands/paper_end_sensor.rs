@@ -1,15 +1,22 @@
-use crate::{command::*, constants::*};
+use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
 struct Handler;
 
-impl CommandHandler for Handler {}
+impl CommandHandler for Handler {
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        context.set_printer_state(PrinterState {
+            paper_end_sensors: *command.data.get(0).unwrap_or(&0u8) & 0b1111,
+            ..context.printer_state.clone()
+        });
+    }
+}
 
 pub fn new() -> Command {
     Command::new(
         "Select Paper End Sensors",
         vec![ESC, 'c' as u8, 3u8],
-        CommandType::Control,
+        CommandType::Context,
         DataType::Single,
         Box::new(Handler {}),
     )
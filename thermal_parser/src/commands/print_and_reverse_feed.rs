@@ -0,0 +1,29 @@
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, _command: &Command, context: &mut Context) {
+        context.line_is_empty = true;
+    }
+
+    fn get_device_command(
+        &self,
+        command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let n = *command.data.get(0).unwrap_or(&0u8);
+        Some(vec![DeviceCommand::Feed(0 - n as i16)])
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Print and Reverse Feed",
+        vec![ESC, 'K' as u8],
+        CommandType::ContextControl,
+        DataType::Single,
+        Box::new(Handler {}),
+    )
+}
@@ -4,6 +4,10 @@ use crate::{command::*, constants::*, context::*};
 struct Handler;
 
 impl CommandHandler for Handler {
+    fn apply_context(&self, _command: &Command, context: &mut Context) {
+        context.line_is_empty = true;
+    }
+
     fn get_device_command(
         &self,
         command: &Command,
@@ -18,7 +22,7 @@ pub fn new() -> Command {
     Command::new(
         "Print and Reverse Feed Lines",
         vec![ESC, 'e' as u8],
-        CommandType::Control,
+        CommandType::ContextControl,
         DataType::Single,
         Box::new(Handler {}),
     )
@@ -5,44 +5,36 @@ struct Handler;
 
 impl CommandHandler for Handler {
     fn apply_context(&self, command: &Command, context: &mut Context) {
-        if context.is_page_mode {
-            let x_l = *command.data.get(0).unwrap_or(&0u8);
-            let x_h = *command.data.get(1).unwrap_or(&0u8);
-            let y_l = *command.data.get(2).unwrap_or(&0u8);
-            let y_h = *command.data.get(3).unwrap_or(&0u8);
-            let dx_l = *command.data.get(4).unwrap_or(&0u8);
-            let dx_h = *command.data.get(5).unwrap_or(&0u8);
-            let dy_l = *command.data.get(6).unwrap_or(&0u8);
-            let dy_h = *command.data.get(7).unwrap_or(&0u8);
-
-            let horizontal_logical_origin =
-                (u16::from(x_l) + u16::from(x_h) * 256) * context.graphics.h_motion_unit as u16;
-            let vertical_logical_origin =
-                (u16::from(y_l) + u16::from(y_h) * 256) * context.graphics.v_motion_unit as u16;
-
-            // Calculate print area dimensions
-            let print_area_width =
-                (u16::from(dx_l) + u16::from(dx_h) * 256) * context.graphics.h_motion_unit as u16;
-            let print_area_height =
-                (u16::from(dy_l) + u16::from(dy_h) * 256) * context.graphics.v_motion_unit as u16;
-
-            // Adjustments for exceeding printable area
-            let adjusted_width =
-                if horizontal_logical_origin + print_area_width > context.graphics.x as u16 {
-                    context.graphics.x as u16 - horizontal_logical_origin
-                } else {
-                    print_area_width
-                };
+        let x_l = *command.data.get(0).unwrap_or(&0u8);
+        let x_h = *command.data.get(1).unwrap_or(&0u8);
+        let y_l = *command.data.get(2).unwrap_or(&0u8);
+        let y_h = *command.data.get(3).unwrap_or(&0u8);
+        let dx_l = *command.data.get(4).unwrap_or(&0u8);
+        let dx_h = *command.data.get(5).unwrap_or(&0u8);
+        let dy_l = *command.data.get(6).unwrap_or(&0u8);
+        let dy_h = *command.data.get(7).unwrap_or(&0u8);
+
+        let width = u32::from(dx_l) + u32::from(dx_h) * 256;
+        let height = u32::from(dy_l) + u32::from(dy_h) * 256;
+
+        //An area without width or height is ignored
+        if width == 0 || height == 0 {
+            return;
+        }
 
-            let adjusted_height =
-                if vertical_logical_origin + print_area_height > context.graphics.y as u16 {
-                    context.graphics.y as u16 - vertical_logical_origin
-                } else {
-                    print_area_height
-                };
+        //Stored in standard mode too, it takes effect once page mode is selected.
+        //Anything past the printable area is cut off by Context::page_area
+        context.graphics.page_area = Some(PageArea {
+            x: (u32::from(x_l) + u32::from(x_h) * 256) * context.motion_unit_x_pixels(),
+            y: (u32::from(y_l) + u32::from(y_h) * 256) * context.motion_unit_y_pixels(),
+            width: width * context.motion_unit_x_pixels(),
+            height: height * context.motion_unit_y_pixels(),
+        });
 
-            context.graphics.y = adjusted_height as usize;
-            context.graphics.x = adjusted_width as usize;
+        //The print position moves to the start of the new area
+        if context.is_page_mode {
+            context.graphics.x = 0;
+            context.graphics.y = 0;
         }
     }
 }
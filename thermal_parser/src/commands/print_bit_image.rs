@@ -0,0 +1,26 @@
+use crate::{command::*, constants::*, context::*, graphics::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn get_graphics(&self, command: &Command, context: &Context) -> Option<GraphicsCommand> {
+        let m = *command.data.get(0).unwrap_or(&0u8);
+
+        context.graphics.download_bit_image.as_ref().map(|img| {
+            let mut img = img.clone();
+            img.stretch = stretch_from_mode(m);
+            GraphicsCommand::Image(img)
+        })
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Print Downloaded Bit Image",
+        vec![GS, '/' as u8],
+        CommandType::Graphics,
+        DataType::Single,
+        Box::new(Handler {}),
+    )
+}
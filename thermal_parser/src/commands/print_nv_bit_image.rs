@@ -0,0 +1,27 @@
+use crate::{command::*, constants::*, context::*, graphics::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn get_graphics(&self, command: &Command, context: &Context) -> Option<GraphicsCommand> {
+        let n = *command.data.get(0).unwrap_or(&0u8) as usize;
+        let m = *command.data.get(1).unwrap_or(&0u8);
+
+        //Images are numbered from 1 in the order they were defined
+        let img = context.graphics.nv_bit_images.get(n.checked_sub(1)?)?;
+        let mut img = img.clone();
+        img.stretch = stretch_from_mode(m);
+        Some(GraphicsCommand::Image(img))
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Print NV Bit Image",
+        vec![FS, 'p' as u8],
+        CommandType::Graphics,
+        DataType::Double,
+        Box::new(Handler {}),
+    )
+}
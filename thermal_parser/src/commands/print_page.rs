@@ -0,0 +1,32 @@
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, _command: &Command, context: &mut Context) {
+        context.line_is_empty = true;
+    }
+
+    fn get_device_command(
+        &self,
+        _command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        //Prints the page and stays in page mode, the page data is kept
+        if context.is_page_mode {
+            return Some(vec![DeviceCommand::PrintPage]);
+        }
+        None
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Print Page",
+        vec![ESC, FF],
+        CommandType::ContextControl,
+        DataType::Empty,
+        Box::new(Handler {}),
+    )
+}
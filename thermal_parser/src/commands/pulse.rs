@@ -1,9 +1,33 @@
-use crate::{command::*, constants::*};
+use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
 struct Handler;
 
-impl CommandHandler for Handler {}
+impl CommandHandler for Handler {
+    //m selects pin 2 or 5, the pulse is on for t1 x 2 ms and off for t2 x 2 ms but never shorter than on
+    fn get_device_command(
+        &self,
+        command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let (m, t1, t2) = (
+            *command.data.get(0)?,
+            *command.data.get(1)?,
+            *command.data.get(2)?,
+        );
+        let pin = match m {
+            0 | 48 => 2,
+            1 | 49 => 5,
+            _ => return None,
+        };
+
+        Some(vec![DeviceCommand::Pulse {
+            pin,
+            on_ms: t1 as u16 * 2,
+            off_ms: t1.max(t2) as u16 * 2,
+        }])
+    }
+}
 
 pub fn new() -> Command {
     Command::new(
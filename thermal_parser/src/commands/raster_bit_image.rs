@@ -14,20 +14,14 @@ struct Handler {
 
 impl CommandHandler for Handler {
     fn get_graphics(&self, command: &Command, _context: &Context) -> Option<GraphicsCommand> {
-        let stretch = match self.scaling {
-            0 | 48 => (1, 1),
-            1 | 49 => (2, 1),
-            2 | 50 => (1, 2),
-            3 | 52 => (2, 2),
-            _ => (1, 1),
-        };
+        let stretch = stretch_from_mode(self.scaling);
 
-        //possibly implement scaling here
         Some(GraphicsCommand::Image(Image {
             pixels: command.data.clone(),
             width: self.width,
             height: self.height,
             pixel_type: PixelType::Monochrome(1),
+            planes: vec![1],
             stretch,
             advances_xy: true,
         }))
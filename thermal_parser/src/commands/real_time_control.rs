@@ -0,0 +1,66 @@
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let function = *command.data.get(0)?;
+
+        match function {
+            //Generate pulse, ignored while there is an error
+            1 if context.printer_state.has_error() => None,
+            //m selects pin 2 or 5, the pulse is on and off for t x 100 ms
+            1 => {
+                let (m, t) = (*command.data.get(1)?, *command.data.get(2)?);
+                if m > 1 || !(1..=8).contains(&t) {
+                    return None;
+                }
+                let pin = if m == 0 { 2 } else { 5 };
+                let ms = t as u16 * 100;
+                Some(vec![DeviceCommand::Pulse {
+                    pin,
+                    on_ms: ms,
+                    off_ms: ms,
+                }])
+            }
+            //Power-off sequence, the power-off notice is sent back
+            2 => Some(vec![DeviceCommand::Transmit(vec![0x3B, 0x30, 0x00])]),
+            //Clear buffers, page data is discarded and the end of clearing is sent back
+            8 => Some(vec![
+                DeviceCommand::Cancel,
+                DeviceCommand::Transmit(vec![0x37, 0x25, 0x00]),
+            ]),
+            _ => None,
+        }
+    }
+
+    //fn followed by m t (1), a b (2), m (7) or d1...d7 (8)
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        let len = match data.first() {
+            Some(1) | Some(2) => 3,
+            Some(7) => 2,
+            Some(8) => 8,
+            _ => 1,
+        };
+        if data.len() < len {
+            data.push(byte);
+            return true;
+        }
+        false
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Real-Time Control",
+        vec![DLE, 0x14],
+        CommandType::RealTime,
+        DataType::Custom, //push is implemented in the CommandHandler for Custom types
+        Box::new(Handler {}),
+    )
+}
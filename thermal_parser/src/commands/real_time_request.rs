@@ -0,0 +1,44 @@
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    //Recovers from recoverable errors, n = 1 restarts printing, n = 2 clears the buffers first.
+    //Ignored unless there is a recoverable error
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        let n = *command.data.get(0).unwrap_or(&0u8);
+        let state = &context.printer_state;
+        if (n != 1 && n != 2) || !(state.recoverable_error || state.autocutter_error) {
+            return;
+        }
+
+        let mut state = state.clone();
+        state.recover();
+        context.set_printer_state(state);
+    }
+
+    //n = 2 clears the buffers, taken before the error is recovered
+    fn get_device_command(
+        &self,
+        command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let state = &context.printer_state;
+        if command.data.first() != Some(&2) || !(state.recoverable_error || state.autocutter_error)
+        {
+            return None;
+        }
+        Some(vec![DeviceCommand::Cancel])
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Send Real-Time Request",
+        vec![DLE, 0x05],
+        CommandType::RealTime,
+        DataType::Single,
+        Box::new(Handler {}),
+    )
+}
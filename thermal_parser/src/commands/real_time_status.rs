@@ -0,0 +1,39 @@
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let n = *command.data.get(0)?;
+        let status = context.printer_state.status_byte(n)?;
+        Some(vec![DeviceCommand::Transmit(vec![status])])
+    }
+
+    //n, the ink (7) and paper sensor (8) requests are followed by a
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        let len = match data.first() {
+            Some(7) | Some(8) => 2,
+            _ => 1,
+        };
+        if data.len() < len {
+            data.push(byte);
+            return true;
+        }
+        false
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Transmit Real-Time Status",
+        vec![DLE, 0x04],
+        CommandType::RealTime,
+        DataType::Custom, //push is implemented in the CommandHandler for Custom types
+        Box::new(Handler {}),
+    )
+}
@@ -1,11 +1,50 @@
-use crate::{command::*, constants::*, subcommands};
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let function = *command.data.get(2)?;
+        let m = *command.data.get(3)?;
+
+        //The process ID d1 - d4 is sent back once the preceding data has been processed
+        match (function, m) {
+            (48, 48) => {
+                let mut response = vec![0x37, 0x22];
+                response.extend(command.data.get(4..8)?);
+                response.push(0x00);
+                Some(vec![DeviceCommand::Transmit(response)])
+            }
+            _ => None,
+        }
+    }
+
+    //pL pH followed by pL + pH * 256 bytes of fn, m and the parameters
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        if data.len() < 2 {
+            data.push(byte);
+            return true;
+        }
+        let len = data[0] as usize + data[1] as usize * 256;
+        if data.len() < len + 2 {
+            data.push(byte);
+            return true;
+        }
+        false
+    }
+}
 
 pub fn new() -> Command {
     Command::new(
         "Request Response Transmission",
         vec![GS, '(' as u8, 'H' as u8],
         CommandType::Control,
-        DataType::Custom,
-        subcommands::new(false, false, subcommands::no_commands()),
+        DataType::Custom, //push is implemented in the CommandHandler for Custom types
+        Box::new(Handler {}),
     )
 }
@@ -5,8 +5,17 @@ use crate::{command::*, constants::*};
 struct Handler;
 
 impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        _command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        //Any page data that wasn't printed is discarded
+        Some(vec![DeviceCommand::EndPage])
+    }
     fn apply_context(&self, _command: &Command, context: &mut Context) {
         context.is_page_mode = false;
+        context.line_is_empty = true;
     }
 }
 
@@ -14,7 +23,7 @@ pub fn new() -> Command {
     Command::new(
         "Select standard mode",
         vec![ESC, 'S' as u8],
-        CommandType::Context,
+        CommandType::ContextControl,
         DataType::Empty,
         Box::new(Handler {}),
     )
@@ -10,7 +10,7 @@ impl CommandHandler for Handler {
         _context: &Context,
     ) -> Option<Vec<DeviceCommand>> {
         let nl = *command.data.get(0).unwrap_or(&0u8);
-        let nh = *command.data.get(0).unwrap_or(&0u8);
+        let nh = *command.data.get(1).unwrap_or(&0u8);
         Some(vec![DeviceCommand::MoveX(nl as u16 + nh as u16 * 256)])
     }
 }
@@ -9,13 +9,12 @@ impl CommandHandler for Handler {
     fn get_device_command(
         &self,
         command: &Command,
-        _context: &Context,
+        context: &Context,
     ) -> Option<Vec<DeviceCommand>> {
-        if _context.is_page_mode {
+        if context.is_page_mode {
             let nl = *command.data.get(0).unwrap_or(&0u8);
             let nh = *command.data.get(1).unwrap_or(&0u8);
-            println!("xL: {}, xH: {}", nl, nh);
-            return Some(vec![DeviceCommand::MoveX(nl as u16 + nh as u16 * 256)]);
+            return Some(vec![DeviceCommand::MoveY(nl as u16 + nh as u16 * 256)]);
         }
         None
     }
@@ -23,7 +22,7 @@ impl CommandHandler for Handler {
 
 pub fn new() -> Command {
     Command::new(
-        "Set Absolute Vertical Print POS", //should be JQuery Command :)
+        "Set Absolute Vertical Print POS",
         vec![GS, '$' as u8],
         CommandType::Control,
         DataType::Double,
@@ -0,0 +1,33 @@
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        context.printer_state.asb_enabled = *command.data.get(0).unwrap_or(&0u8) & 0b1111;
+    }
+
+    //The current status is sent as soon as any status is enabled
+    fn get_device_command(
+        &self,
+        _command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        if context.printer_state.asb_enabled == 0 {
+            return None;
+        }
+        let status = context.printer_state.asb_status().to_vec();
+        Some(vec![DeviceCommand::Transmit(status)])
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Enable/Disable Automatic Status Back",
+        vec![GS, 'a' as u8],
+        CommandType::ContextControl,
+        DataType::Single,
+        Box::new(Handler {}),
+    )
+}
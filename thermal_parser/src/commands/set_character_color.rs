@@ -0,0 +1,57 @@
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+//See https://reference.epson-biz.com/modules/ref_escpos/index.php?content_id=256
+impl CommandHandler for Handler {
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        let function = *command.data.get(2).unwrap_or(&0u8);
+        let m = *command.data.get(3).unwrap_or(&0u8);
+
+        match function {
+            48 => {
+                if let Some(color) = Color::from_raw(m) {
+                    context.text.color = color;
+                }
+            }
+            49 => {
+                if let Some(color) = Color::from_raw(m) {
+                    context.text.background_color = color;
+                }
+            }
+            50 => {
+                let a = *command.data.get(4).unwrap_or(&48u8);
+                context.text.shadow_color = match m {
+                    1 | 49 => Color::from_raw(a).unwrap_or(context.text.shadow_color.clone()),
+                    _ => Color::None,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    //pL pH followed by pL + pH * 256 bytes of fn, m and a
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        if data.len() < 2 {
+            data.push(byte);
+            return true;
+        }
+        let len = data[0] as usize + data[1] as usize * 256;
+        if data.len() < len + 2 {
+            data.push(byte);
+            return true;
+        }
+        false
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Select Character Color",
+        vec![GS, '(' as u8, 'N' as u8],
+        CommandType::Context,
+        DataType::Custom, //push is implemented in the CommandHandler for Custom types
+        Box::new(Handler {}),
+    )
+}
@@ -10,7 +10,7 @@ impl CommandHandler for Handler {
 
         //last 4,5,6 bits masked
         //11110101 -> 00000101
-        context.text.height_mult = 0b00000111 & n + 1;
+        context.text.height_mult = (0b00000111 & n) + 1;
 
         //bit 1,2,3 masked and shifted all the way to the right // ***
         //01010101 -> 01010000 -> 00000101
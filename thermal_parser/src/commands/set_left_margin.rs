@@ -0,0 +1,26 @@
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        if !context.is_line_start() {
+            return;
+        }
+        let nl = *command.data.get(0).unwrap_or(&0u8);
+        let nh = *command.data.get(1).unwrap_or(&0u8);
+        context.graphics.print_left_margin =
+            (nl as u32 + nh as u32 * 256) * context.motion_unit_x_pixels();
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Set Left Margin",
+        vec![GS, 'L' as u8],
+        CommandType::Context,
+        DataType::Double,
+        Box::new(Handler {}),
+    )
+}
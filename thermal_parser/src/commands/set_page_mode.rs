@@ -5,8 +5,22 @@ use crate::{command::*, constants::*};
 struct Handler;
 
 impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        _command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        if context.is_page_mode {
+            return Some(vec![DeviceCommand::BeginPage]);
+        }
+        None
+    }
     fn apply_context(&self, _command: &Command, context: &mut Context) {
+        if !context.is_line_start() {
+            return;
+        }
         context.is_page_mode = true;
+        context.line_is_empty = true;
     }
 }
 
@@ -14,7 +28,7 @@ pub fn new() -> Command {
     Command::new(
         "Set page mode",
         vec![ESC, 'L' as u8],
-        CommandType::Context,
+        CommandType::ContextControl,
         DataType::Empty,
         Box::new(Handler {}),
     )
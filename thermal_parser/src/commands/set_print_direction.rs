@@ -0,0 +1,27 @@
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        let n = *command.data.get(0).unwrap_or(&0u8);
+        context.graphics.page_direction = PrintDirection::from_raw(n);
+
+        //The print position moves to the start of the area in the new direction
+        if context.is_page_mode {
+            context.graphics.x = 0;
+            context.graphics.y = 0;
+        }
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Set Print Direction",
+        vec![ESC, 'T' as u8],
+        CommandType::Context,
+        DataType::Single,
+        Box::new(Handler {}),
+    )
+}
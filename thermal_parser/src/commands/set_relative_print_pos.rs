@@ -0,0 +1,29 @@
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        command: &Command,
+        _context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let nl = *command.data.get(0).unwrap_or(&0u8);
+        let nh = *command.data.get(1).unwrap_or(&0u8);
+        //Negative values are sent as two's complement (65536 - n)
+        Some(vec![DeviceCommand::MoveXRelative(
+            (nl as u16 + nh as u16 * 256) as i16,
+        )])
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Set Relative Print POS",
+        vec![ESC, '\\' as u8],
+        CommandType::Control,
+        DataType::Double,
+        Box::new(Handler {}),
+    )
+}
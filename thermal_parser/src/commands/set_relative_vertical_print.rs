@@ -1,15 +1,31 @@
-use crate::{command::*, constants::*};
+use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
 struct Handler;
 
-impl CommandHandler for Handler {}
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        if context.is_page_mode {
+            let nl = *command.data.get(0).unwrap_or(&0u8);
+            let nh = *command.data.get(1).unwrap_or(&0u8);
+            //Negative values are sent as two's complement (65536 - n)
+            return Some(vec![DeviceCommand::MoveYRelative(
+                (nl as u16 + nh as u16 * 256) as i16,
+            )]);
+        }
+        None
+    }
+}
 
 pub fn new() -> Command {
     Command::new(
         "Set Relative Vertical Print",
         vec![GS, '\\' as u8],
-        CommandType::Context,
+        CommandType::Control,
         DataType::Double,
         Box::new(Handler {}),
     )
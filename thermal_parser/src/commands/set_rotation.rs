@@ -0,0 +1,25 @@
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        let n = *command.data.get(0).unwrap_or(&0u8);
+        context.text.rotation = match n {
+            1 | 49 => TextRotation::OneDotSpacing,
+            2 | 50 => TextRotation::OneAndHalfDotSpacing,
+            _ => TextRotation::Off,
+        };
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Set 90 Degree Clockwise Rotation",
+        vec![ESC, 'V' as u8],
+        CommandType::Context,
+        DataType::Single,
+        Box::new(Handler {}),
+    )
+}
@@ -5,6 +5,9 @@ struct Handler;
 
 impl CommandHandler for Handler {
     fn apply_context(&self, command: &Command, context: &mut Context) {
+        if !context.is_line_start() {
+            return;
+        }
         let n = *command.data.get(0).unwrap_or(&0u8);
         context.text.upside_down = (n & 0x00000001) == 1;
     }
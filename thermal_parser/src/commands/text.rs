@@ -29,6 +29,16 @@ impl CommandHandler for Handler {
     fn debug(&self, command: &Command, context: &Context) -> String {
         self.get_text(command, context).unwrap_or("".to_string())
     }
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        //Line feeds print the line buffer, anything else fills it
+        if let Some(text) = self.get_text(command, context) {
+            if text.ends_with('\n') {
+                context.line_is_empty = true;
+            } else if text.chars().any(|c| c != '\r') {
+                context.line_is_empty = false;
+            }
+        }
+    }
 
     //TODO: impl apply_context trait fn, to detect the encoding of the text, and set codec.
 }
@@ -1,9 +1,36 @@
-use crate::{command::*, constants::*};
+use crate::{command::*, constants::*, context::*};
 
 #[derive(Clone)]
 struct Handler;
 
-impl CommandHandler for Handler {}
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let n = *command.data.get(0)?;
+        let profile = &context.profile;
+
+        //IDs are a single byte, information is sent as _ followed by the text and NUL
+        let info = match n {
+            1 | 49 => return Some(vec![DeviceCommand::Transmit(vec![profile.model_id])]),
+            2 | 50 => return Some(vec![DeviceCommand::Transmit(vec![profile.type_id])]),
+            3 | 51 => return Some(vec![DeviceCommand::Transmit(vec![profile.version_id])]),
+            65 => &profile.firmware_version,
+            66 => &profile.manufacturer,
+            67 => &profile.model_name,
+            68 => &profile.serial_number,
+            69 => &profile.fonts,
+            _ => return None,
+        };
+
+        let mut response = vec![0x5F];
+        response.extend(info.as_bytes());
+        response.push(0x00);
+        Some(vec![DeviceCommand::Transmit(response)])
+    }
+}
 
 pub fn new() -> Command {
     Command::new(
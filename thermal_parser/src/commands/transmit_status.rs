@@ -0,0 +1,26 @@
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let n = *command.data.get(0)?;
+        let status = context.printer_state.transmit_status_byte(n)?;
+        Some(vec![DeviceCommand::Transmit(vec![status])])
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Transmit Status",
+        vec![GS, 'r' as u8],
+        CommandType::Control,
+        DataType::Single,
+        Box::new(Handler {}),
+    )
+}
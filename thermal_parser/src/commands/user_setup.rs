@@ -0,0 +1,87 @@
+use crate::{command::*, constants::*, context::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn apply_context(&self, command: &Command, context: &mut Context) {
+        let function = *command.data.get(2).unwrap_or(&0u8);
+
+        //Memory switches as a switch number followed by 8 characters of 0, 1 or 2 to leave the bit
+        if function == 3 {
+            for setting in command.data.get(3..).unwrap_or(&[]).chunks(9) {
+                let index = setting[0] as usize;
+                if !(1..=8).contains(&index) || setting.len() < 9 {
+                    continue;
+                }
+                let switch = &mut context.profile.memory_switches[index - 1];
+                for (i, bit) in setting[1..].iter().enumerate() {
+                    match bit {
+                        48 => *switch &= !(1 << (7 - i)),
+                        49 => *switch |= 1 << (7 - i),
+                        _ => {}
+                    }
+                }
+            }
+        }
+    }
+
+    fn get_device_command(
+        &self,
+        command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        let function = *command.data.get(2)?;
+        let a = *command.data.get(3).unwrap_or(&0u8);
+
+        let response = match function {
+            //Entering the user setting mode is confirmed
+            1 => vec![0x37, 0x20, 0x00],
+            //Memory switch as 8 characters of 0 or 1, the most significant bit first
+            4 => {
+                let switch = *context
+                    .profile
+                    .memory_switches
+                    .get((a as usize).checked_sub(1)?)?;
+                let mut response = vec![0x37, 0x21];
+                response.extend((0..8).map(|i| if switch & 1 << (7 - i) != 0 { 49 } else { 48 }));
+                response.push(0x00);
+                response
+            }
+            //Customized setting value as decimal text
+            6 => {
+                let value = *context.profile.customized_values.get(&a).unwrap_or(&0);
+                let mut response = vec![0x37, 0x27];
+                response.extend(value.to_string().as_bytes());
+                response.push(0x00);
+                response
+            }
+            _ => return None,
+        };
+        Some(vec![DeviceCommand::Transmit(response)])
+    }
+
+    //pL pH followed by pL + pH * 256 bytes of fn and the parameters
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        if data.len() < 2 {
+            data.push(byte);
+            return true;
+        }
+        let len = data[0] as usize + data[1] as usize * 256;
+        if data.len() < len + 2 {
+            data.push(byte);
+            return true;
+        }
+        false
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Set User Setup Commands",
+        vec![GS, '(' as u8, 'E' as u8],
+        CommandType::ContextControl,
+        DataType::Custom, //push is implemented in the CommandHandler for Custom types
+        Box::new(Handler {}),
+    )
+}
@@ -0,0 +1,41 @@
+use crate::{command::*, constants::*, context::*, graphics::*};
+
+#[derive(Clone)]
+struct Handler;
+
+impl CommandHandler for Handler {
+    fn get_graphics(&self, command: &Command, _context: &Context) -> Option<GraphicsCommand> {
+        let m = *command.data.get(0)?;
+        let x = *command.data.get(1)? as u32 + *command.data.get(2)? as u32 * 256;
+        let y = *command.data.get(3)? as u32 + *command.data.get(4)? as u32 * 256;
+
+        let mut img = Image::from_column_bit_image(command.data.get(5..)?, x, y * 8);
+        img.stretch = stretch_from_mode(m);
+        Some(GraphicsCommand::Image(img))
+    }
+
+    //m xL xH yL yH, x dots wide and y bytes high followed by x * y bytes of column data
+    fn push(&mut self, data: &mut Vec<u8>, byte: u8) -> bool {
+        if data.len() < 5 {
+            data.push(byte);
+            return true;
+        }
+        let x = data[1] as usize + data[2] as usize * 256;
+        let y = data[3] as usize + data[4] as usize * 256;
+        if data.len() < x * y + 5 {
+            data.push(byte);
+            return true;
+        }
+        false
+    }
+}
+
+pub fn new() -> Command {
+    Command::new(
+        "Print Variable Vertical Size Bit Image",
+        vec![GS, 'Q' as u8, '0' as u8],
+        CommandType::Graphics,
+        DataType::Custom, //push is implemented in the CommandHandler for Custom types
+        Box::new(Handler {}),
+    )
+}
@@ -1,7 +1,12 @@
+use crate::command::DeviceCommand;
 use crate::graphics;
 use std::collections::HashMap;
 
-use crate::graphics::{Image, ImageRef};
+use crate::graphics::{Image, ImageRef, ImageRefStorage};
+use crate::nv_store::NvGraphicsStore;
+use crate::response::ResponseSink;
+use std::io;
+use std::sync::Arc;
 
 #[derive(Clone, PartialEq)]
 pub enum TextJustify {
@@ -24,6 +29,14 @@ pub enum TextUnderline {
     Double,
 }
 
+//90° clockwise rotation (ESC V) and the spacing added between rotated characters
+#[derive(Clone, PartialEq)]
+pub enum TextRotation {
+    Off,
+    OneDotSpacing,
+    OneAndHalfDotSpacing,
+}
+
 #[derive(Clone, PartialEq)]
 pub enum Font {
     A,
@@ -50,6 +63,43 @@ impl Font {
     }
 }
 
+//Page mode print direction (ESC T) named by the way a line of text runs on the paper
+#[derive(Clone, PartialEq, Debug)]
+pub enum PrintDirection {
+    LeftToRight, //starts at the upper left
+    BottomToTop, //starts at the lower left
+    RightToLeft, //starts at the lower right
+    TopToBottom, //starts at the upper right
+}
+
+impl PrintDirection {
+    pub fn from_raw(byte: u8) -> PrintDirection {
+        match byte {
+            1 | 49 => PrintDirection::BottomToTop,
+            2 | 50 => PrintDirection::RightToLeft,
+            3 | 51 => PrintDirection::TopToBottom,
+            _ => PrintDirection::LeftToRight,
+        }
+    }
+
+    //Lines run along the paper's length when rotated a quarter turn
+    pub fn is_vertical(&self) -> bool {
+        matches!(
+            self,
+            PrintDirection::BottomToTop | PrintDirection::TopToBottom
+        )
+    }
+}
+
+//Page mode print area (ESC W) in pixels from the top left of the page
+#[derive(Clone, PartialEq, Debug)]
+pub struct PageArea {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
 #[derive(Clone)]
 pub enum HumanReadableInterface {
     None,
@@ -58,12 +108,26 @@ pub enum HumanReadableInterface {
     Both,
 }
 
-#[derive(Clone)]
+//Printer inks, None is not printed
+#[derive(Clone, PartialEq, Debug)]
 pub enum Color {
+    None,
     Black,
     Red,
 }
 
+impl Color {
+    //Color 1 and color 2 of GS ( N, color 3 isn't available on two color printers
+    pub fn from_raw(byte: u8) -> Option<Color> {
+        match byte {
+            48 => Some(Color::None),
+            49 => Some(Color::Black),
+            50 => Some(Color::Red),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Context {
     pub default: Option<Box<Context>>,
@@ -72,6 +136,23 @@ pub struct Context {
     pub code2d: Code2DContext,
     pub graphics: GraphicsContext,
     pub is_page_mode: bool,
+    //When set, text is held in the line buffer until the line is printed and
+    //commands only accepted at the beginning of a line are ignored mid-line
+    pub emulate_line_buffer: bool,
+    pub line_is_empty: bool,
+    //Text and moves of the line that hasn't been printed yet
+    pub line_buffer: Vec<LineBufferItem>,
+    //Receives the replies of commands that transmit data back to the host
+    pub response_sink: Option<Arc<dyn ResponseSink + Send + Sync>>,
+    pub profile: PrinterProfile,
+    pub printer_state: PrinterState,
+}
+
+//Line buffer data, text keeps the settings it was received with
+#[derive(Clone)]
+pub enum LineBufferItem {
+    Text(TextContext, String),
+    Move(DeviceCommand),
 }
 
 #[derive(Clone)]
@@ -82,15 +163,20 @@ pub struct TextContext {
     pub justify: TextJustify,
     pub font: Font,
     pub bold: bool,
+    pub double_strike: bool,
     pub italic: bool,
     pub underline: TextUnderline,
-    pub strikethrough: TextStrikethrough,
+    pub strikethrough: TextStrikethrough, //not an ESC/POS feature, double strike is ESC G
     pub invert: bool,
     pub width_mult: u8,
     pub height_mult: u8,
     pub upside_down: bool,
+    pub rotation: TextRotation,
     pub line_spacing: u8,
+    pub char_spacing: u8, //right-side spacing in motion units
     pub color: Color,
+    pub background_color: Color,
+    pub shadow_color: Color, //character shading, None when off
     pub smoothing: bool,
     pub tab_len: u8,  //character width for tabs
     pub is_cjk: bool, //set flag if enter cjk mode
@@ -100,15 +186,109 @@ pub struct TextContext {
 pub struct GraphicsContext {
     pub x: usize,
     pub y: usize,
+    pub y_max: usize, //furthest y the paper was fed to, used to limit reverse feeding
+    pub reverse_feed_max: u32, //motion units
     pub paper_width: f32,
     pub margin_left: f32,
     pub margin_right: f32,
+    pub print_left_margin: u32, //pixels from the left edge of the printable area
+    pub print_width: Option<u32>, //pixels, None uses the rest of the printable area
+    pub page_area: Option<PageArea>, //None uses the whole printable width
+    pub page_height: f32,       //inches, height of the page area when none is set
+    pub page_direction: PrintDirection,
     pub dots_per_inch: u16,
     pub v_motion_unit: u8,
     pub h_motion_unit: u8,
     pub graphics_count: u16,
     pub stored_graphics: HashMap<ImageRef, Image>,
     pub buffer_graphics: Option<Image>,
+    pub download_bit_image: Option<Image>, //GS *, printed with GS /
+    pub nv_bit_images: Vec<Image>,         //FS q, printed by number (1 - n) with FS p
+    pub nv_store: Option<Arc<dyn NvGraphicsStore + Send + Sync>>, //keeps NV graphics between sessions
+    pub nv_store_error: Option<Arc<io::Error>>, //last failed NV store update, memory is updated anyway
+    pub nv_graphics_capacity: u32,              //bytes
+    pub download_graphics_capacity: u32,        //bytes
+}
+
+impl GraphicsContext {
+    pub fn graphics_capacity(&self, storage: &ImageRefStorage) -> u32 {
+        match storage {
+            ImageRefStorage::Disc => self.nv_graphics_capacity,
+            ImageRefStorage::Ram => self.download_graphics_capacity,
+        }
+    }
+
+    //Bytes of image data used by the graphics defined in the storage
+    pub fn graphics_bytes_used(&self, storage: &ImageRefStorage) -> u32 {
+        self.stored_graphics
+            .iter()
+            .filter(|(k, _)| k.storage == *storage)
+            .map(|(_, img)| img.pixels.len() as u32)
+            .sum()
+    }
+
+    pub fn graphics_bytes_remaining(&self, storage: &ImageRefStorage) -> u32 {
+        self.graphics_capacity(storage)
+            .saturating_sub(self.graphics_bytes_used(storage))
+    }
+
+    pub fn graphics_key_codes(&self, storage: &ImageRefStorage) -> Vec<(u8, u8)> {
+        let mut key_codes = self
+            .stored_graphics
+            .keys()
+            .filter(|k| k.storage == *storage)
+            .map(|k| (k.kc1, k.kc2))
+            .collect::<Vec<(u8, u8)>>();
+        key_codes.sort();
+        key_codes
+    }
+
+    //NV graphics are written through to the NV store, graphics that don't fit in the
+    //remaining capacity are discarded
+    pub fn store_graphics(&mut self, img_ref: ImageRef, img: Image) {
+        let replaced = self
+            .stored_graphics
+            .get(&img_ref)
+            .map(|old| old.pixels.len() as u32)
+            .unwrap_or(0);
+        let remaining = self.graphics_bytes_remaining(&img_ref.storage) + replaced;
+        if img.pixels.len() as u32 > remaining {
+            return;
+        }
+
+        if let (Some(store), ImageRefStorage::Disc) = (&self.nv_store, &img_ref.storage) {
+            let saved = store.save(&img_ref, &img);
+            self.record_nv_store_error(saved);
+        }
+        self.stored_graphics.insert(img_ref, img);
+    }
+
+    pub fn remove_graphics(&mut self, img_ref: &ImageRef) {
+        self.stored_graphics.remove(img_ref);
+        if let (Some(store), ImageRefStorage::Disc) = (&self.nv_store, &img_ref.storage) {
+            let removed = store.remove(img_ref);
+            self.record_nv_store_error(removed);
+        }
+    }
+
+    pub fn clear_graphics(&mut self, storage: ImageRefStorage) {
+        let cleared = self
+            .stored_graphics
+            .keys()
+            .filter(|k| k.storage == storage)
+            .cloned()
+            .collect::<Vec<ImageRef>>();
+        for img_ref in cleared {
+            self.remove_graphics(&img_ref);
+        }
+    }
+
+    //Store errors don't stop printing, the last one is kept for the caller to check
+    fn record_nv_store_error(&mut self, result: io::Result<()>) {
+        if let Err(err) = result {
+            self.nv_store_error = Some(Arc::new(err));
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -154,6 +334,223 @@ pub struct Code2DContext {
     pub datamatrix_width: u8,
 }
 
+//Simulated state of the printer mechanism, reported by the real time status
+#[derive(Clone)]
+pub struct PrinterState {
+    pub drawer_pin_high: bool, //drawer kick-out connector pin 3
+    pub cover_open: bool,
+    pub feed_button_pressed: bool,
+    pub feeding_by_button: bool,
+    pub paper_near_end: bool,
+    pub paper_end: bool,
+    pub recoverable_error: bool,
+    pub autocutter_error: bool,
+    pub unrecoverable_error: bool,
+    pub auto_recoverable_error: bool,
+    //GS a, bits select drawer, online, error and paper sensor changes that are sent back
+    pub asb_enabled: u8,
+    //Length of the paper roll in dot rows, None for a roll that never runs out
+    pub paper_roll_rows: Option<u32>,
+    pub paper_rows_used: u32,
+    //The near-end sensor detects when this many dot rows are left on the roll
+    pub paper_near_end_rows: u32,
+    //The next cut jams and stays jammed until it's cleared
+    pub cutter_jammed: bool,
+    //ESC c 3, bits 0-1 near-end and 2-3 paper end sensors that output the parallel
+    //interface paper-end signal. It is only kept, there is no parallel interface to drive
+    pub paper_end_sensors: u8,
+    //ESC c 4, bits 0-1 near-end sensors that stop printing
+    pub print_stop_sensors: u8,
+    //Drawer kick-out connector pin the simulated cash drawer is wired to, 0 for none
+    pub drawer_pin: u8,
+}
+
+impl Default for PrinterState {
+    fn default() -> Self {
+        PrinterState {
+            drawer_pin_high: false,
+            cover_open: false,
+            feed_button_pressed: false,
+            feeding_by_button: false,
+            paper_near_end: false,
+            paper_end: false,
+            recoverable_error: false,
+            autocutter_error: false,
+            unrecoverable_error: false,
+            auto_recoverable_error: false,
+            asb_enabled: 0,
+            paper_roll_rows: None,
+            paper_rows_used: 0,
+            paper_near_end_rows: 8000, //about a meter at 203 dpi
+            cutter_jammed: false,
+            paper_end_sensors: 0b1111,
+            print_stop_sensors: 0,
+            drawer_pin: 2,
+        }
+    }
+}
+
+impl PrinterState {
+    pub fn has_error(&self) -> bool {
+        self.recoverable_error
+            || self.autocutter_error
+            || self.unrecoverable_error
+            || self.auto_recoverable_error
+    }
+
+    //Printing stops while the printer is offline
+    pub fn is_offline(&self) -> bool {
+        self.cover_open
+            || self.feeding_by_button
+            || self.paper_end
+            || self.has_error()
+            || (self.print_stop_sensors & 0b0011 != 0 && self.paper_near_end)
+    }
+
+    //Starts a new roll of paper, None for a roll that never runs out
+    pub fn load_paper(&mut self, rows: Option<u32>) {
+        self.paper_roll_rows = rows;
+        self.paper_rows_used = 0;
+        self.feed_paper(0);
+    }
+
+    //Uses up dot rows of the paper roll and updates the paper sensors
+    pub fn feed_paper(&mut self, rows: u32) {
+        let Some(roll_rows) = self.paper_roll_rows else {
+            return;
+        };
+        self.paper_rows_used = roll_rows.min(self.paper_rows_used.saturating_add(rows));
+        let remaining = roll_rows - self.paper_rows_used;
+        self.paper_near_end = remaining <= self.paper_near_end_rows;
+        self.paper_end = remaining == 0;
+    }
+
+    //A jammed cutter stops printing with an autocutter error
+    pub fn cut(&mut self) {
+        if self.cutter_jammed {
+            self.autocutter_error = true;
+        }
+    }
+
+    //A pulse on the drawer's pin opens it, its open switch pulls pin 3 high until it's closed
+    pub fn kick_drawer(&mut self, pin: u8) {
+        if pin == self.drawer_pin {
+            self.drawer_pin_high = true;
+        }
+    }
+
+    //DLE ENQ, recoverable errors are cleared once their cause is gone
+    pub fn recover(&mut self) {
+        if self.unrecoverable_error {
+            return;
+        }
+        self.recoverable_error = false;
+        if !self.cutter_jammed {
+            self.autocutter_error = false;
+        }
+    }
+
+    //Automatic status back, bit 4 of the first byte is always set
+    pub fn asb_status(&self) -> [u8; 4] {
+        let bit = |set: bool, bit: u8| if set { 1 << bit } else { 0 };
+        [
+            0x10 | bit(self.drawer_pin_high, 2)
+                | bit(self.is_offline(), 3)
+                | bit(self.cover_open, 5)
+                | bit(self.feeding_by_button, 6),
+            bit(self.recoverable_error, 2)
+                | bit(self.autocutter_error, 3)
+                | bit(self.unrecoverable_error, 5)
+                | bit(self.auto_recoverable_error, 6),
+            bit(self.paper_near_end, 0)
+                | bit(self.paper_near_end, 1)
+                | bit(self.paper_end, 2)
+                | bit(self.paper_end, 3),
+            0x00,
+        ]
+    }
+
+    //Whether a change to the other state is sent back with automatic status back
+    pub fn asb_changed(&self, other: &PrinterState) -> bool {
+        let enabled = [
+            (0b0001, self.drawer_pin_high != other.drawer_pin_high),
+            (
+                0b0010,
+                self.is_offline() != other.is_offline()
+                    || self.cover_open != other.cover_open
+                    || self.feeding_by_button != other.feeding_by_button,
+            ),
+            (0b0100, self.asb_status()[1] != other.asb_status()[1]),
+            (0b1000, self.asb_status()[2] != other.asb_status()[2]),
+        ];
+        enabled
+            .iter()
+            .any(|(mask, changed)| self.asb_enabled & mask != 0 && *changed)
+    }
+
+    //GS r n, paper sensor (1) and drawer kick-out connector (2) status
+    pub fn transmit_status_byte(&self, n: u8) -> Option<u8> {
+        match n {
+            1 | 49 => Some(
+                if self.paper_near_end { 0x03 } else { 0 } | if self.paper_end { 0x0C } else { 0 },
+            ),
+            2 | 50 => Some(if self.drawer_pin_high { 0x01 } else { 0 }),
+            _ => None,
+        }
+    }
+
+    //DLE EOT n, bits 1 and 4 are always set
+    pub fn status_byte(&self, n: u8) -> Option<u8> {
+        let bits: &[(bool, u8)] = match n {
+            1 => &[
+                (self.drawer_pin_high, 2),
+                (self.is_offline(), 3),
+                (self.feed_button_pressed, 6),
+            ],
+            2 => &[
+                (self.cover_open, 2),
+                (self.feeding_by_button, 3),
+                (self.paper_end, 5),
+                (self.has_error(), 6),
+            ],
+            3 => &[
+                (self.recoverable_error, 2),
+                (self.autocutter_error, 3),
+                (self.unrecoverable_error, 5),
+                (self.auto_recoverable_error, 6),
+            ],
+            4 => &[
+                (self.paper_near_end, 2),
+                (self.paper_near_end, 3),
+                (self.paper_end, 5),
+                (self.paper_end, 6),
+            ],
+            _ => return None,
+        };
+
+        Some(
+            bits.iter()
+                .filter(|(set, _)| *set)
+                .fold(0x12, |status, (_, bit)| status | 1 << bit),
+        )
+    }
+}
+
+//What the printer reports about itself to the host, drivers query it when they connect
+#[derive(Clone)]
+pub struct PrinterProfile {
+    pub model_id: u8,
+    pub type_id: u8, //bit 1 is set when an autocutter is installed
+    pub version_id: u8,
+    pub firmware_version: String,
+    pub manufacturer: String,
+    pub model_name: String,
+    pub serial_number: String,
+    pub fonts: String, //additional fonts of languages, e.g. KANJI JAPANESE
+    pub memory_switches: [u8; 8],
+    pub customized_values: HashMap<u8, u16>,
+}
+
 impl Context {
     fn default() -> Context {
         Context {
@@ -165,6 +562,7 @@ impl Context {
                 justify: TextJustify::Left,
                 font: Font::A,
                 bold: false,
+                double_strike: false,
                 italic: false,
                 underline: TextUnderline::Off,
                 strikethrough: TextStrikethrough::Off,
@@ -172,8 +570,12 @@ impl Context {
                 width_mult: 1,
                 height_mult: 1,
                 upside_down: false,
-                line_spacing: 30, //pixels
+                rotation: TextRotation::Off,
+                line_spacing: 30, //motion units
+                char_spacing: 0,
                 color: Color::Black,
+                background_color: Color::None,
+                shadow_color: Color::None,
                 smoothing: false,
                 tab_len: 10,
                 is_cjk: false,
@@ -213,17 +615,47 @@ impl Context {
             graphics: GraphicsContext {
                 x: 0,
                 y: 0,
-                paper_width: 3.0,   //inches
-                margin_left: 0.1,   //inches
-                margin_right: 0.1,  //inches
+                y_max: 0,
+                reverse_feed_max: 60, //motion units, two lines at the default line spacing
+                paper_width: 3.0,     //inches
+                margin_left: 0.1,     //inches
+                margin_right: 0.1,    //inches
+                print_left_margin: 0,
+                print_width: None,
+                page_area: None,
+                page_height: 1662.0 / 360.0, //inches, the ESC W default of 1662 dots at 360 dpi
+                page_direction: PrintDirection::LeftToRight,
                 dots_per_inch: 210, //pixels
                 v_motion_unit: 1,   //Pixels
                 h_motion_unit: 1,   //Pixels
                 graphics_count: 0,
                 stored_graphics: HashMap::<ImageRef, Image>::new(),
                 buffer_graphics: None,
+                download_bit_image: None,
+                nv_bit_images: vec![],
+                nv_store: None,
+                nv_store_error: None,
+                nv_graphics_capacity: 262144,
+                download_graphics_capacity: 65536,
             },
             is_page_mode: false,
+            emulate_line_buffer: false,
+            line_is_empty: true,
+            line_buffer: vec![],
+            response_sink: None,
+            printer_state: PrinterState::default(),
+            profile: PrinterProfile {
+                model_id: 0x20,
+                type_id: 0x02,
+                version_id: 0x40,
+                firmware_version: "30.00 ESC/POS".to_string(),
+                manufacturer: "EPSON".to_string(),
+                model_name: "TM-T88V".to_string(),
+                serial_number: "000000000000".to_string(),
+                fonts: "".to_string(),
+                memory_switches: [0; 8],
+                customized_values: HashMap::new(),
+            },
         }
     }
 
@@ -234,16 +666,87 @@ impl Context {
         new_context
     }
 
+    //Loads the NV graphics kept in the store, graphics defined or cleared later are kept in sync
+    pub fn set_nv_store(
+        &mut self,
+        store: Arc<dyn NvGraphicsStore + Send + Sync>,
+    ) -> io::Result<()> {
+        self.graphics.stored_graphics.extend(store.load()?);
+        self.graphics.nv_store = Some(store);
+        Ok(())
+    }
+
+    //Changes the simulated printer state, changes enabled with GS a are sent back right away
+    pub fn set_printer_state(&mut self, state: PrinterState) {
+        let changed = self.printer_state.asb_changed(&state);
+        self.printer_state = state;
+        if changed {
+            self.transmit(&self.printer_state.asb_status());
+        }
+    }
+
+    //Printed dot rows use up the paper roll, which may change the paper sensors
+    pub fn feed_paper(&mut self, rows: u32) {
+        let mut state = self.printer_state.clone();
+        state.feed_paper(rows);
+        self.set_printer_state(state);
+    }
+
+    pub fn transmit(&self, bytes: &[u8]) {
+        if let Some(sink) = &self.response_sink {
+            sink.transmit(bytes);
+        }
+    }
+
     pub fn reset(&mut self) {
         if let Some(default) = &self.default {
             self.text = default.text.clone();
             self.barcode = default.barcode.clone();
             self.code2d = default.code2d.clone();
+            //The paper position isn't a setting, so it survives a reset
+            let (x, y, y_max) = (self.graphics.x, self.graphics.y, self.graphics.y_max);
+            //Neither does anything kept in non-volatile memory
+            let mut nv_graphics = std::mem::take(&mut self.graphics.stored_graphics);
+            nv_graphics.retain(|k, _| k.storage == ImageRefStorage::Disc);
+            let nv_bit_images = std::mem::take(&mut self.graphics.nv_bit_images);
+            let nv_store = self.graphics.nv_store.take();
+            let nv_store_error = self.graphics.nv_store_error.take();
+
             self.graphics = default.graphics.clone();
+            self.graphics.x = x;
+            self.graphics.y = y;
+            self.graphics.y_max = y_max;
+            self.graphics.stored_graphics = nv_graphics;
+            self.graphics.nv_bit_images = nv_bit_images;
+            self.graphics.nv_store = nv_store;
+            self.graphics.nv_store_error = nv_store_error;
         }
+        self.is_page_mode = false;
+        self.line_is_empty = true;
+        //Data in the line buffer is discarded
+        self.line_buffer.clear();
+        self.set_printer_state(PrinterState {
+            paper_end_sensors: 0b1111,
+            print_stop_sensors: 0,
+            ..self.printer_state.clone()
+        });
     }
 
-    pub fn available_width_pixels(&self) -> u32 {
+    //Strikethrough isn't part of ESC/POS so no command sets it, it's kept
+    //in the defaults as well so that it survives ESC @
+    pub fn set_strikethrough(&mut self, strikethrough: TextStrikethrough) {
+        if let Some(default) = &mut self.default {
+            default.text.strikethrough = strikethrough.clone();
+        }
+        self.text.strikethrough = strikethrough;
+    }
+
+    //Justification, margins and upside down mode only apply at the beginning of a line
+    pub fn is_line_start(&self) -> bool {
+        !self.emulate_line_buffer || self.line_is_empty
+    }
+
+    pub fn printable_width_pixels(&self) -> u32 {
         let print_area =
             self.graphics.paper_width - (self.graphics.margin_left + self.graphics.margin_right);
         let print_area_pixels = print_area * self.graphics.dots_per_inch as f32;
@@ -252,6 +755,61 @@ impl Context {
         // TODO: maybe 58mm width paper(margin 5mm left and right) with 203dpi, (58 - 10) / 25.4 * 203 = 384 dots.
     }
 
+    //Print area of the page limited to the printable width
+    pub fn page_area(&self) -> PageArea {
+        let printable_width = self.printable_width_pixels();
+        match &self.graphics.page_area {
+            Some(area) => {
+                let x = area.x.min(printable_width);
+                PageArea {
+                    x,
+                    y: area.y,
+                    width: area.width.min(printable_width - x),
+                    height: area.height,
+                }
+            }
+            None => PageArea {
+                x: 0,
+                y: 0,
+                width: printable_width,
+                height: (self.graphics.page_height * self.graphics.dots_per_inch as f32) as u32,
+            },
+        }
+    }
+
+    //Width and height of the page area as seen along the print direction
+    pub fn page_area_logical_size(&self) -> (u32, u32) {
+        let area = self.page_area();
+        if self.graphics.page_direction.is_vertical() {
+            (area.height, area.width)
+        } else {
+            (area.width, area.height)
+        }
+    }
+
+    //Left margin set by GS L, limited to the printable area
+    pub fn print_area_left_pixels(&self) -> u32 {
+        //The page area takes the place of the margins in page mode
+        if self.is_page_mode {
+            return 0;
+        }
+        self.graphics
+            .print_left_margin
+            .min(self.printable_width_pixels())
+    }
+
+    //Width left for printing after the left margin and print area width are applied
+    pub fn available_width_pixels(&self) -> u32 {
+        if self.is_page_mode {
+            return self.page_area_logical_size().0;
+        }
+        let remaining = self.printable_width_pixels() - self.print_area_left_pixels();
+        match self.graphics.print_width {
+            Some(width) => width.min(remaining),
+            None => remaining,
+        }
+    }
+
     // Font size specifications may come in points(pounds) or pixels where:
     // 1 pixel (px) is usually assumed to be 1/96th of an inch, or 96dpi.
     // 1 point (pt) is assumed to be 1/72nd of an inch.
@@ -272,6 +830,10 @@ impl Context {
     }
 
     pub fn graphics_x_offset(&self, width: u32) -> u32 {
+        self.print_area_left_pixels() + self.justify_offset(width)
+    }
+
+    fn justify_offset(&self, width: u32) -> u32 {
         if width > self.available_width_pixels() {
             return 0;
         }
@@ -297,6 +859,33 @@ impl Context {
         self.graphics.h_motion_unit as u32
     }
 
+    //Right-side character spacing, doubled along with the character width
+    pub fn char_spacing_pixels(&self) -> u32 {
+        self.text.char_spacing as u32 * self.motion_unit_x_pixels() * self.text.width_mult as u32
+    }
+
+    //Rotation only applies in standard mode
+    pub fn is_rotated(&self) -> bool {
+        !self.is_page_mode && self.text.rotation != TextRotation::Off
+    }
+
+    //Upside-down printing only applies in standard mode, page mode uses the print direction
+    pub fn is_upside_down(&self) -> bool {
+        !self.is_page_mode && self.text.upside_down
+    }
+
+    //Spacing between rotated characters, half dots are rounded up
+    pub fn rotation_spacing_pixels(&self) -> u32 {
+        if !self.is_rotated() {
+            return 0;
+        }
+        match self.text.rotation {
+            TextRotation::Off => 0,
+            TextRotation::OneDotSpacing => 1,
+            TextRotation::OneAndHalfDotSpacing => 2,
+        }
+    }
+
     pub fn line_height_pixels(&self) -> u32 {
         self.text.line_spacing as u32 * self.motion_unit_y_pixels() as u32
     }
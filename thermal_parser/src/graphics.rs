@@ -1,4 +1,4 @@
-use crate::context::HumanReadableInterface;
+use crate::context::{Color, HumanReadableInterface};
 
 #[derive(Clone)]
 pub struct Barcode {
@@ -39,7 +39,8 @@ pub enum PixelType {
     MonochromeByte,
     //1 bit per pixel one color, the u8 selects the color (1 - 4)
     Monochrome(u8),
-    //the first u8 selects the color (1 - 4), second how many colors are in the data
+    //the first u8 selects the color (1 - 4), second how many colors are in the data,
+    //the bit planes of the colors make up the tone of each pixel
     MultipleTone(u8, u8),
     Unknown,
 }
@@ -50,6 +51,8 @@ pub struct Image {
     pub width: u32,
     pub height: u32,
     pub pixel_type: PixelType,
+    //Color (1 - 4) of each 1 bit per pixel plane, the planes follow each other in pixels
+    pub planes: Vec<u8>,
     pub stretch: (u8, u8),
     pub advances_xy: bool,
 }
@@ -70,40 +73,131 @@ impl Image {
         data
     }
 
+    /// Width and height in dots once the horizontal and vertical expansion is applied.
+    pub fn stretched_size(&self) -> (u32, u32) {
+        (
+            self.width * self.stretch.0.max(1) as u32,
+            self.height * self.stretch.1.max(1) as u32,
+        )
+    }
+
     /// Always returns 1 pixel per byte.
     pub fn as_grayscale(&self) -> Vec<u8> {
         if self.pixel_type == PixelType::MonochromeByte {
             return self.pixels.clone();
         }
-        let mut bytes = Vec::<u8>::new();
 
-        //number of bytes we need to use for the last column of each row of data
-        let mut padding = self.width % 8;
-        if padding == 0 {
-            padding = 8;
+        let planes = self.planes_as_grayscale();
+        if planes.len() < 2 {
+            return planes
+                .into_iter()
+                .next()
+                .map(|(_, plane)| plane)
+                .unwrap_or_default();
         }
-        let mut col = 0;
 
-        for byte in &self.pixels {
-            col += 8;
-            if col >= self.width {
-                for n in 0..padding {
-                    bytes.push(if *byte & 1 << (7 - n) != 0 { 0 } else { 255 });
-                }
-                col = 0;
-            } else {
-                bytes.push(if *byte & 1 << 7 != 0 { 0 } else { 255 });
-                bytes.push(if *byte & 1 << 6 != 0 { 0 } else { 255 });
-                bytes.push(if *byte & 1 << 5 != 0 { 0 } else { 255 });
-                bytes.push(if *byte & 1 << 4 != 0 { 0 } else { 255 });
-                bytes.push(if *byte & 1 << 3 != 0 { 0 } else { 255 });
-                bytes.push(if *byte & 1 << 2 != 0 { 0 } else { 255 });
-                bytes.push(if *byte & 1 << 1 != 0 { 0 } else { 255 });
-                bytes.push(if *byte & 1 << 0 != 0 { 0 } else { 255 });
-            }
+        let size = (self.width * self.height) as usize;
+
+        //Colors are printed on top of each other
+        if let PixelType::Monochrome(_) = self.pixel_type {
+            return (0..size)
+                .map(|i| {
+                    planes
+                        .iter()
+                        .map(|(_, plane)| plane[i])
+                        .min()
+                        .unwrap_or(255)
+                })
+                .collect();
+        }
+
+        //Each plane is a bit of the tone, color 1 being the most significant
+        let mut sorted = planes;
+        sorted.sort_by_key(|(color, _)| *color);
+        let max_level = (1u32 << sorted.len()) - 1;
+
+        (0..size)
+            .map(|i| {
+                let level = sorted.iter().fold(0u32, |level, (_, plane)| {
+                    (level << 1) | if plane[i] == 0 { 1 } else { 0 }
+                });
+                (255 - level * 255 / max_level) as u8
+            })
+            .collect()
+    }
+
+    /// The color of each pixel, only for monochrome images
+    /// that are printed with more than the first color
+    pub fn as_colors(&self) -> Option<Vec<Color>> {
+        if !matches!(self.pixel_type, PixelType::Monochrome(_)) || !self.planes.contains(&2) {
+            return None;
+        }
+
+        let planes = self.planes_as_grayscale();
+        let size = (self.width * self.height) as usize;
+
+        //Color 1 shows where it is printed over color 2
+        Some(
+            (0..size)
+                .map(|i| {
+                    let mut color = Color::None;
+                    for (c, plane) in &planes {
+                        if plane[i] != 0 {
+                            continue;
+                        }
+                        match c {
+                            2 if color == Color::None => color = Color::Red,
+                            2 => {}
+                            _ => color = Color::Black,
+                        }
+                    }
+                    color
+                })
+                .collect(),
+        )
+    }
+
+    //Adds the plane of another color of the same image, a color
+    //that is already in the image starts over with the other image
+    pub fn with_plane(self, other: Image) -> Image {
+        let same_kind =
+            std::mem::discriminant(&self.pixel_type) == std::mem::discriminant(&other.pixel_type);
+        let is_new_color = other
+            .planes
+            .iter()
+            .all(|color| !self.planes.contains(color));
+
+        if !same_kind || !is_new_color || self.width != other.width || self.height != other.height {
+            return other;
         }
 
-        bytes
+        let mut image = self;
+        image.pixels.extend(other.pixels);
+        image.planes.extend(other.planes);
+        if let PixelType::MultipleTone(color, _) = image.pixel_type {
+            image.pixel_type = PixelType::MultipleTone(color, image.planes.len() as u8);
+        }
+        image
+    }
+
+    fn planes_as_grayscale(&self) -> Vec<(u8, Vec<u8>)> {
+        let count = self.planes.len().max(1);
+        let plane_len = self.pixels.len() / count;
+        if plane_len == 0 {
+            return vec![];
+        }
+
+        self.pixels
+            .chunks(plane_len)
+            .take(count)
+            .enumerate()
+            .map(|(i, plane)| {
+                //keep every plane exactly width * height so they can be combined
+                let mut gray = plane_as_grayscale(plane, self.width);
+                gray.resize((self.width * self.height) as usize, 255);
+                (*self.planes.get(i).unwrap_or(&1), gray)
+            })
+            .collect()
     }
 
     pub fn from_raster_data(data: &Vec<u8>) -> Option<Image> {
@@ -122,9 +216,11 @@ impl Image {
         let width = x1 as u32 + x2 as u32 * 256;
         let height = y1 as u32 + y2 as u32 * 256;
 
+        //c is the color of the data (49 - 52)
+        let color = c.saturating_sub(48);
         let pixel_type = match a {
-            48 => PixelType::Monochrome(c),
-            52 => PixelType::MultipleTone(c, 1),
+            48 => PixelType::Monochrome(color),
+            52 => PixelType::MultipleTone(color, 1),
             _ => PixelType::Unknown,
         };
 
@@ -137,6 +233,7 @@ impl Image {
             width,
             height,
             pixel_type,
+            planes: vec![color],
             stretch,
             advances_xy: true,
         })
@@ -161,19 +258,19 @@ impl Image {
         let width = x1 as u32 + x2 as u32 * 256;
         let height = y1 as u32 + y2 as u32 * 256;
 
-        //b (above) specifies number of color data stored,
-        // we are ignoring this for now if b > 1
+        //b (above) specifies number of color data stored
         // [byte(color) bytes(capacity)] [byte(color) bytes(capacity)]
+        let plane_len = (width as usize).div_ceil(8) * height as usize;
+        let (planes, pixels) = split_planes(&data[8..], b, plane_len);
+
         let pixel_type = match a {
-            48 => PixelType::Monochrome(1),
-            52 => PixelType::MultipleTone(1, b),
+            48 => PixelType::Monochrome(*planes.first().unwrap_or(&1)),
+            52 => PixelType::MultipleTone(*planes.first().unwrap_or(&1), planes.len() as u8),
             _ => PixelType::Unknown,
         };
 
         let stretch = (1, 1);
 
-        let pixels = data[8..].to_vec();
-
         Some((
             ImageRef { kc1, kc2, storage },
             Image {
@@ -181,6 +278,7 @@ impl Image {
                 width,
                 height,
                 pixel_type,
+                planes,
                 stretch,
                 advances_xy: true,
             },
@@ -203,9 +301,11 @@ impl Image {
         let width = x1 as u32 + x2 as u32 * 256;
         let height = y1 as u32 + y2 as u32 * 256;
 
+        //c is the color of the data (49 - 52)
+        let color = c.saturating_sub(48);
         let pixel_type = match a {
-            48 => PixelType::Monochrome(c),
-            52 => PixelType::MultipleTone(c, 1),
+            48 => PixelType::Monochrome(color),
+            52 => PixelType::MultipleTone(color, 1),
             _ => PixelType::Unknown,
         };
 
@@ -218,6 +318,7 @@ impl Image {
             width,
             height,
             pixel_type,
+            planes: vec![color],
             stretch,
             advances_xy: false,
         })
@@ -242,19 +343,19 @@ impl Image {
         let width = x1 as u32 + x2 as u32 * 256;
         let height = y1 as u32 + y2 as u32 * 256;
 
-        //b (above) specifies number of color data stored,
-        // we are ignoring this for now if b > 1
+        //b (above) specifies number of color data stored
         // [byte(color) bytes(capacity)] [byte(color) bytes(capacity)]
+        let plane_len = width as usize * (height as usize).div_ceil(8);
+        let (planes, pixels) = split_planes(&data[8..], b, plane_len);
+
         let pixel_type = match a {
-            48 => PixelType::Monochrome(1),
-            52 => PixelType::MultipleTone(1, b),
+            48 => PixelType::Monochrome(*planes.first().unwrap_or(&1)),
+            52 => PixelType::MultipleTone(*planes.first().unwrap_or(&1), planes.len() as u8),
             _ => PixelType::Unknown,
         };
 
         let stretch = (1, 1);
 
-        let pixels = data[8..].to_vec();
-
         Some((
             ImageRef { kc1, kc2, storage },
             Image {
@@ -262,11 +363,175 @@ impl Image {
                 width,
                 height,
                 pixel_type,
+                planes,
                 stretch,
                 advances_xy: false,
             },
         ))
     }
+
+    /// Column format data of the legacy bit image commands, height / 8 bytes per column.
+    /// Columns missing from data that was cut short are left white
+    pub fn from_column_bit_image(data: &[u8], width: u32, height: u32) -> Image {
+        let mut columns = data.to_vec();
+        columns.resize(width as usize * height.div_ceil(8) as usize, 0);
+
+        Image {
+            pixels: column_to_raster(&columns, width as usize, height as usize),
+            width,
+            height,
+            pixel_type: PixelType::MonochromeByte,
+            planes: vec![],
+            stretch: (1, 1),
+            advances_xy: true,
+        }
+    }
+
+    /// Decodes an uncompressed 1, 4 or 8 bit Windows BMP file.
+    /// tone 48 prints the dots darker than half in color (1 - 4), tone 52 keeps the gray levels
+    pub fn from_bmp(data: &[u8], tone: u8, color: u8) -> Option<Image> {
+        let u16_at = |at: usize| Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?));
+        let u32_at = |at: usize| Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?));
+
+        if data.get(0..2)? != b"BM" || u32_at(30)? != 0 {
+            return None;
+        }
+
+        let offset = u32_at(10)? as usize;
+        let header_size = u32_at(14)? as usize;
+        let width = u32_at(18)? as i32;
+        let height = u32_at(22)? as i32;
+        let bit_count = u16_at(28)? as usize;
+        if width <= 0 || height == 0 || !matches!(bit_count, 1 | 4 | 8) {
+            return None;
+        }
+
+        //The palette follows the header as blue, green, red, reserved
+        let colors = match u32_at(46).unwrap_or(0) as usize {
+            0 => 1 << bit_count,
+            n => n.min(1 << bit_count),
+        };
+        let palette = (0..colors)
+            .map(|i| {
+                let at = 14 + header_size + i * 4;
+                let (b, g, r) = (*data.get(at)?, *data.get(at + 1)?, *data.get(at + 2)?);
+                Some(((r as u32 * 299 + g as u32 * 587 + b as u32 * 114) / 1000) as u8)
+            })
+            .collect::<Option<Vec<u8>>>()?;
+
+        //Rows are padded to 4 bytes and stored bottom up unless the height is negative
+        let (width, rows) = (width as usize, height.unsigned_abs() as usize);
+        let stride = (width * bit_count).div_ceil(32) * 4;
+        let per_byte = 8 / bit_count;
+
+        //The header sizes can't be trusted before the pixel data is known to be there
+        if rows.checked_mul(stride)?.checked_add(offset)? > data.len() {
+            return None;
+        }
+        let mut gray = Vec::<u8>::with_capacity(width * rows);
+
+        for row in 0..rows {
+            let src_row = if height > 0 { rows - 1 - row } else { row };
+            let line = data.get(offset + src_row * stride..offset + (src_row + 1) * stride)?;
+            for x in 0..width {
+                let byte = line[x / per_byte];
+                let shift = 8 - bit_count * (x % per_byte + 1);
+                let index = (byte >> shift) as usize & ((1 << bit_count) - 1);
+                gray.push(*palette.get(index).unwrap_or(&255));
+            }
+        }
+
+        let (pixels, pixel_type, planes) = if tone == 52 {
+            (gray, PixelType::MonochromeByte, vec![])
+        } else {
+            let color = match color.saturating_sub(48) {
+                1..=4 => color - 48,
+                _ => 1,
+            };
+            (
+                grayscale_as_plane(&gray, width),
+                PixelType::Monochrome(color),
+                vec![color],
+            )
+        };
+
+        Some(Image {
+            pixels,
+            width: width as u32,
+            height: rows as u32,
+            pixel_type,
+            planes,
+            stretch: (1, 1),
+            advances_xy: true,
+        })
+    }
+}
+
+//Packs 1 byte per pixel into a 1 bit per pixel raster plane, dots darker than half are printed
+fn grayscale_as_plane(gray: &[u8], width: usize) -> Vec<u8> {
+    let mut plane = Vec::<u8>::new();
+    for row in gray.chunks(width) {
+        for dots in row.chunks(8) {
+            plane.push(dots.iter().enumerate().fold(0u8, |byte, (i, dot)| {
+                if *dot < 128 {
+                    byte | 1 << (7 - i)
+                } else {
+                    byte
+                }
+            }));
+        }
+    }
+    plane
+}
+
+//Unpacks a 1 bit per pixel raster plane into 1 byte per pixel
+fn plane_as_grayscale(plane: &[u8], width: u32) -> Vec<u8> {
+    let mut bytes = Vec::<u8>::new();
+
+    //number of bytes we need to use for the last column of each row of data
+    let mut padding = width % 8;
+    if padding == 0 {
+        padding = 8;
+    }
+    let mut col = 0;
+
+    for byte in plane {
+        col += 8;
+        if col >= width {
+            for n in 0..padding {
+                bytes.push(if *byte & 1 << (7 - n) != 0 { 0 } else { 255 });
+            }
+            col = 0;
+        } else {
+            bytes.push(if *byte & 1 << 7 != 0 { 0 } else { 255 });
+            bytes.push(if *byte & 1 << 6 != 0 { 0 } else { 255 });
+            bytes.push(if *byte & 1 << 5 != 0 { 0 } else { 255 });
+            bytes.push(if *byte & 1 << 4 != 0 { 0 } else { 255 });
+            bytes.push(if *byte & 1 << 3 != 0 { 0 } else { 255 });
+            bytes.push(if *byte & 1 << 2 != 0 { 0 } else { 255 });
+            bytes.push(if *byte & 1 << 1 != 0 { 0 } else { 255 });
+            bytes.push(if *byte & 1 << 0 != 0 { 0 } else { 255 });
+        }
+    }
+
+    bytes
+}
+
+//Splits stored graphics data into the colors of its planes and
+//the plane data, each plane is led by its color (49 - 52)
+fn split_planes(data: &[u8], count: u8, plane_len: usize) -> (Vec<u8>, Vec<u8>) {
+    let mut planes = Vec::<u8>::new();
+    let mut pixels = Vec::<u8>::with_capacity(plane_len * count as usize);
+
+    for chunk in data.chunks(plane_len + 1).take(count.max(1) as usize) {
+        if chunk.len() < plane_len + 1 {
+            break;
+        }
+        planes.push(chunk[0].saturating_sub(48));
+        pixels.extend_from_slice(&chunk[1..]);
+    }
+
+    (planes, pixels)
 }
 
 /// Converts column data, which is encoded in
@@ -312,6 +577,37 @@ pub fn column_to_raster(pixels: &[u8], final_width: usize, final_height: usize)
     flip_right_to_left(rot, final_width, final_height)
 }
 
+/// Horizontal and vertical expansion of the bit image modes (0 - 3 or 48 - 51):
+/// normal, double width, double height and quadruple
+pub fn stretch_from_mode(m: u8) -> (u8, u8) {
+    match m {
+        1 | 49 => (2, 1),
+        2 | 50 => (1, 2),
+        3 | 51 => (2, 2),
+        _ => (1, 1),
+    }
+}
+
+/// Repeats each pixel (1 per item) by the horizontal and vertical expansion of the stretch.
+pub fn stretch_pixels<T: Clone>(pixels: &[T], width: usize, stretch: (u8, u8)) -> Vec<T> {
+    let (sx, sy) = (stretch.0.max(1) as usize, stretch.1.max(1) as usize);
+    if (sx, sy) == (1, 1) || width == 0 {
+        return pixels.to_vec();
+    }
+
+    let mut stretched = Vec::<T>::with_capacity(pixels.len() * sx * sy);
+    for row in pixels.chunks(width) {
+        let start = stretched.len();
+        for pixel in row {
+            stretched.extend(std::iter::repeat_n(pixel.clone(), sx));
+        }
+        for _ in 1..sy {
+            stretched.extend_from_within(start..start + row.len() * sx);
+        }
+    }
+    stretched
+}
+
 fn rotate_90_clockwise(data: Vec<u8>, width: usize, height: usize) -> Vec<u8> {
     let mut result = vec![0; data.len()];
 
@@ -4,7 +4,9 @@ pub mod commands;
 pub mod constants;
 pub mod context;
 pub mod graphics;
+pub mod nv_store;
 pub mod parser;
+pub mod response;
 pub mod subcommands;
 pub mod util;
 
@@ -0,0 +1,127 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::graphics::{Image, ImageRef, ImageRefStorage, PixelType};
+
+/// Keeps NV graphics between parse sessions the way a printer keeps them between power cycles.
+/// The context loads everything when the store is set and updates it on define and clear.
+pub trait NvGraphicsStore {
+    fn load(&self) -> io::Result<Vec<(ImageRef, Image)>>;
+    fn save(&self, img_ref: &ImageRef, img: &Image) -> io::Result<()>;
+    fn remove(&self, img_ref: &ImageRef) -> io::Result<()>;
+}
+
+/// Stores each NV graphic in a directory as a file named after its key codes
+/// holding the image metadata followed by the pixel data.
+///
+/// The metadata is `NVG1`, the width and height as little endian u32, the pixel
+/// type and its two parameters, the horizontal and vertical expansion, 1 if the
+/// image advances the print position, then the plane count and the planes.
+pub struct DirectoryNvStore {
+    pub dir: PathBuf,
+}
+
+const MAGIC: &[u8; 4] = b"NVG1";
+const EXTENSION: &str = "nvg";
+
+impl DirectoryNvStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        DirectoryNvStore { dir: dir.into() }
+    }
+
+    fn path(&self, img_ref: &ImageRef) -> PathBuf {
+        self.dir.join(format!(
+            "{:02X}{:02X}.{}",
+            img_ref.kc1, img_ref.kc2, EXTENSION
+        ))
+    }
+}
+
+impl NvGraphicsStore for DirectoryNvStore {
+    fn load(&self) -> io::Result<Vec<(ImageRef, Image)>> {
+        if !self.dir.exists() {
+            return Ok(vec![]);
+        }
+
+        let mut graphics = vec![];
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(EXTENSION) {
+                continue;
+            }
+            let key = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+            let (Ok(kc1), Ok(kc2)) = (
+                u8::from_str_radix(key.get(0..2).unwrap_or(""), 16),
+                u8::from_str_radix(key.get(2..4).unwrap_or(""), 16),
+            ) else {
+                continue;
+            };
+
+            //Files that can't be read back are skipped like undefined key codes
+            if let Some(img) = decode(&fs::read(&path)?) {
+                let storage = ImageRefStorage::Disc;
+                graphics.push((ImageRef { kc1, kc2, storage }, img));
+            }
+        }
+        Ok(graphics)
+    }
+
+    fn save(&self, img_ref: &ImageRef, img: &Image) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.path(img_ref), encode(img))
+    }
+
+    fn remove(&self, img_ref: &ImageRef) -> io::Result<()> {
+        match fs::remove_file(self.path(img_ref)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+}
+
+//magic, width, height, pixel type, stretch, advances, plane count and planes, pixels
+fn encode(img: &Image) -> Vec<u8> {
+    let pixel_type = match img.pixel_type {
+        PixelType::MonochromeByte => [0, 0, 0],
+        PixelType::Monochrome(c) => [1, c, 0],
+        PixelType::MultipleTone(c, n) => [2, c, n],
+        PixelType::Unknown => [3, 0, 0],
+    };
+
+    let mut bytes = MAGIC.to_vec();
+    bytes.extend(img.width.to_le_bytes());
+    bytes.extend(img.height.to_le_bytes());
+    bytes.extend(pixel_type);
+    bytes.extend([img.stretch.0, img.stretch.1, img.advances_xy as u8]);
+    bytes.push(img.planes.len() as u8);
+    bytes.extend(&img.planes);
+    bytes.extend(&img.pixels);
+    bytes
+}
+
+fn decode(bytes: &[u8]) -> Option<Image> {
+    if bytes.get(0..4)? != MAGIC {
+        return None;
+    }
+    let width = u32::from_le_bytes(bytes.get(4..8)?.try_into().ok()?);
+    let height = u32::from_le_bytes(bytes.get(8..12)?.try_into().ok()?);
+    let meta = bytes.get(12..19)?;
+    let pixel_type = match meta[0] {
+        0 => PixelType::MonochromeByte,
+        1 => PixelType::Monochrome(meta[1]),
+        2 => PixelType::MultipleTone(meta[1], meta[2]),
+        _ => PixelType::Unknown,
+    };
+    let planes_end = 19 + meta[6] as usize;
+
+    Some(Image {
+        pixels: bytes.get(planes_end..)?.to_vec(),
+        width,
+        height,
+        pixel_type,
+        planes: bytes.get(19..planes_end)?.to_vec(),
+        stretch: (meta[3], meta[4]),
+        advances_xy: meta[5] != 0,
+    })
+}
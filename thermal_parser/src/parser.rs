@@ -9,11 +9,21 @@ pub struct Parser {
     current_command: Option<Command>,
     current_command_is_default: bool,
     command_buffer: Vec<u8>,
+    real_time_commands: Vec<Command>,
+    real_time_buffer: Vec<u8>,
+    real_time_command: Option<Command>,
     on_command_found: Box<dyn FnMut(Command)>,
 }
 
 impl Parser {
     pub fn new(cmd_set: CommandSet, on_command_found: Box<dyn FnMut(Command)>) -> Self {
+        let real_time_commands = cmd_set
+            .commands
+            .iter()
+            .filter(|cmd| cmd.kind == CommandType::RealTime)
+            .cloned()
+            .collect();
+
         Self {
             cmd_set,
             match_depth: 0,
@@ -21,6 +31,9 @@ impl Parser {
             current_command_is_default: false,
             command_buffer: Vec::<u8>::new(),
             current_command: None,
+            real_time_commands,
+            real_time_buffer: Vec::<u8>::new(),
+            real_time_command: None,
             on_command_found,
         }
     }
@@ -29,6 +42,7 @@ impl Parser {
         self.emit_command(self.cmd_set.begin_parsing.clone());
 
         for byte in bytes {
+            self.parse_real_time(*byte);
             self.parse(byte);
         }
 
@@ -46,9 +60,16 @@ impl Parser {
         self.command_buffer.clear();
         self.command_matches.clear();
         self.current_command_is_default = false;
+        self.real_time_buffer.clear();
+        self.real_time_command = None;
     }
 
     fn emit_command(&mut self, mut cmd: Command) {
+        //Real time commands were already emitted when they were received
+        if cmd.kind == CommandType::RealTime {
+            return;
+        }
+
         if cmd.kind == CommandType::Subcommand {
             let command = &mut cmd;
 
@@ -60,6 +81,48 @@ impl Parser {
         }
     }
 
+    //Real time commands are matched separately from the other commands, the bytes
+    //are still parsed as usual afterwards so they stay part of any command data
+    fn parse_real_time(&mut self, byte: u8) {
+        if let Some(cmd) = &mut self.real_time_command {
+            cmd.push(byte);
+            if is_complete(cmd) {
+                let cmd = self.real_time_command.take().unwrap();
+                (self.on_command_found)(cmd);
+            }
+            return;
+        }
+
+        self.real_time_buffer.push(byte);
+        let mut matches = self
+            .real_time_commands
+            .iter()
+            .filter(|cmd| cmd.commands.starts_with(&self.real_time_buffer));
+
+        //A mismatch can still be the beginning of another real time command
+        let Some(matched) = matches.next() else {
+            self.real_time_buffer.clear();
+            if self
+                .real_time_commands
+                .iter()
+                .any(|cmd| cmd.commands.first() == Some(&byte))
+            {
+                self.real_time_buffer.push(byte);
+            }
+            return;
+        };
+
+        if matched.commands.len() == self.real_time_buffer.len() {
+            let cmd = matched.clone();
+            self.real_time_buffer.clear();
+            if is_complete(&cmd) {
+                (self.on_command_found)(cmd);
+            } else {
+                self.real_time_command = Some(cmd);
+            }
+        }
+    }
+
     fn parse(&mut self, byte: &u8) {
         //If a command is willing to accept bytes and it is not the
         // default command, we don't need to do any filtering
@@ -156,3 +219,8 @@ impl Parser {
         self.match_depth += 1;
     }
 }
+
+//A command is complete when it won't accept another byte
+fn is_complete(cmd: &Command) -> bool {
+    !cmd.clone().push(0)
+}
@@ -0,0 +1,30 @@
+use std::sync::{Mutex, PoisonError};
+
+/// Receives the bytes that commands send back to the host, e.g. an emulator's connection to a driver.
+pub trait ResponseSink {
+    fn transmit(&self, bytes: &[u8]);
+}
+
+/// Keeps the transmitted bytes until they are taken, for test harnesses and emulators that poll.
+#[derive(Default)]
+pub struct ResponseBuffer {
+    bytes: Mutex<Vec<u8>>,
+}
+
+impl ResponseBuffer {
+    pub fn new() -> Self {
+        ResponseBuffer::default()
+    }
+
+    pub fn take(&self) -> Vec<u8> {
+        let mut bytes = self.bytes.lock().unwrap_or_else(PoisonError::into_inner);
+        std::mem::take(&mut *bytes)
+    }
+}
+
+impl ResponseSink for ResponseBuffer {
+    fn transmit(&self, bytes: &[u8]) {
+        let mut buffered = self.bytes.lock().unwrap_or_else(PoisonError::into_inner);
+        buffered.extend_from_slice(bytes);
+    }
+}
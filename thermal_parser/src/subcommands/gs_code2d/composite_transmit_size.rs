@@ -1,10 +1,16 @@
-use crate::command::*;
+use crate::{command::*, context::*, subcommands::gs_code2d::*};
 
 #[derive(Clone)]
 pub struct Handler;
 
 impl CommandHandler for Handler {
-    //TODO implement transmit
+    fn get_device_command(
+        &self,
+        _command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        symbol_size_response(context)
+    }
 }
 
 pub fn new() -> Command {
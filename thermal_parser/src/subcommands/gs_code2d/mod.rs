@@ -1,6 +1,7 @@
 use std::rc::Rc;
 
-use crate::command::Command;
+use crate::command::{Command, DeviceCommand};
+use crate::context::Context;
 
 mod aztec_print;
 mod aztec_set_correction_level;
@@ -89,3 +90,28 @@ pub fn all() -> Rc<Vec<Command>> {
 
     Rc::new(all)
 }
+
+//Header 37h 36h, the horizontal and vertical size in dots as decimal text separated by 1Fh,
+//then 30h when the stored symbol can be printed or 31h when it can't and NUL
+pub fn symbol_size_response(context: &Context) -> Option<Vec<DeviceCommand>> {
+    let (width, height, printable) = match &context.code2d.symbol_storage {
+        Some(code2d) if code2d.width > 0 => {
+            let rows = code2d.points.len() as u32 / code2d.width;
+            (
+                code2d.width * code2d.point_width,
+                rows * code2d.point_height,
+                true,
+            )
+        }
+        _ => (0, 0, false),
+    };
+
+    let mut response = vec![0x37, 0x36];
+    response.extend(width.to_string().as_bytes());
+    response.push(0x1F);
+    response.extend(height.to_string().as_bytes());
+    response.push(0x1F);
+    response.push(if printable { 0x30 } else { 0x31 });
+    response.push(0x00);
+    Some(vec![DeviceCommand::Transmit(response)])
+}
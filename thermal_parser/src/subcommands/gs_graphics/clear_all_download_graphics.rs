@@ -5,10 +5,7 @@ pub struct Handler;
 
 impl CommandHandler for Handler {
     fn apply_context(&self, _command: &Command, context: &mut Context) {
-        context
-            .graphics
-            .stored_graphics
-            .retain(|k, _| k.storage != ImageRefStorage::Ram);
+        context.graphics.clear_graphics(ImageRefStorage::Ram);
     }
 }
 
@@ -6,7 +6,7 @@ pub struct Handler;
 impl CommandHandler for Handler {
     fn apply_context(&self, command: &Command, context: &mut Context) {
         if let Some(img_ref) = ImageRef::from_data(&command.data, ImageRefStorage::Disc) {
-            context.graphics.stored_graphics.remove(&img_ref);
+            context.graphics.remove_graphics(&img_ref);
         }
     }
 }
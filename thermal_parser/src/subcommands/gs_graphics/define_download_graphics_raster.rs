@@ -8,7 +8,7 @@ impl CommandHandler for Handler {
         if let Some((img_ref, img)) =
             Image::from_raster_data_with_ref(&command.data, ImageRefStorage::Ram)
         {
-            context.graphics.stored_graphics.insert(img_ref, img);
+            context.graphics.store_graphics(img_ref, img);
         }
     }
 }
@@ -1,13 +1,22 @@
-use crate::command::*;
+use crate::{command::*, context::*, graphics::*, subcommands::gs_graphics::*};
 
 #[derive(Clone)]
 pub struct Handler;
 
 impl CommandHandler for Handler {
-    //TODO implement transmit
+    fn get_device_command(
+        &self,
+        _command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        key_code_response(
+            0x76,
+            context.graphics.graphics_key_codes(&ImageRefStorage::Ram),
+        )
+    }
 }
 
-//Transmits the defined NV graphics key code list.
+//Transmits the defined download graphics key code list.
 pub fn new() -> Command {
     Command::new(
         "Get Download RAM Key Codes",
@@ -0,0 +1,30 @@
+use crate::{command::*, context::*, graphics::*, subcommands::gs_graphics::*};
+
+#[derive(Clone)]
+pub struct Handler;
+
+impl CommandHandler for Handler {
+    fn get_device_command(
+        &self,
+        _command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        capacity_response(
+            0x32,
+            context
+                .graphics
+                .graphics_bytes_remaining(&ImageRefStorage::Ram),
+        )
+    }
+}
+
+//Transmits the number of bytes of remaining memory (unused area) in the download graphics area.
+pub fn new() -> Command {
+    Command::new(
+        "Get Download Remaining Capacity",
+        vec![4, 52],
+        CommandType::Control,
+        DataType::Subcommand,
+        Box::new(Handler),
+    )
+}
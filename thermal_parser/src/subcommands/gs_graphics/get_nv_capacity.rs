@@ -1,10 +1,16 @@
-use crate::command::*;
+use crate::{command::*, context::*, subcommands::gs_graphics::*};
 
 #[derive(Clone)]
 pub struct Handler;
 
 impl CommandHandler for Handler {
-    //TODO implement transmit
+    fn get_device_command(
+        &self,
+        _command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        capacity_response(0x30, context.graphics.nv_graphics_capacity)
+    }
 }
 
 //Transmits the entire capacity of the NV graphics area (number of bytes in the NV graphics area).
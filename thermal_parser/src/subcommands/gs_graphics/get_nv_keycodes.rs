@@ -1,10 +1,19 @@
-use crate::command::*;
+use crate::{command::*, context::*, graphics::*, subcommands::gs_graphics::*};
 
 #[derive(Clone)]
 pub struct Handler;
 
 impl CommandHandler for Handler {
-    //TODO implement transmit
+    fn get_device_command(
+        &self,
+        _command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        key_code_response(
+            0x72,
+            context.graphics.graphics_key_codes(&ImageRefStorage::Disc),
+        )
+    }
 }
 
 //Transmits the defined NV graphics key code list.
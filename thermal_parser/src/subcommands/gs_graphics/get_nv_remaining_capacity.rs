@@ -1,17 +1,28 @@
-use crate::command::*;
+use crate::{command::*, context::*, graphics::*, subcommands::gs_graphics::*};
 
 #[derive(Clone)]
 pub struct Handler;
 
 impl CommandHandler for Handler {
-    //TODO implement transmit
+    fn get_device_command(
+        &self,
+        _command: &Command,
+        context: &Context,
+    ) -> Option<Vec<DeviceCommand>> {
+        capacity_response(
+            0x31,
+            context
+                .graphics
+                .graphics_bytes_remaining(&ImageRefStorage::Disc),
+        )
+    }
 }
 
 //Transmits the number of bytes of remaining memory (unused area) in the NV graphics area.
 pub fn new() -> Command {
     Command::new(
         "Get NV Remaining Capacity",
-        vec![4, 52],
+        vec![3, 51],
         CommandType::Control,
         DataType::Subcommand,
         Box::new(Handler),
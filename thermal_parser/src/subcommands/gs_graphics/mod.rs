@@ -11,6 +11,7 @@ pub mod define_download_graphics_raster;
 pub mod define_nv_graphics_column;
 pub mod define_nv_graphics_raster;
 pub mod get_download_keycodes;
+pub mod get_download_remaining_capacity;
 pub mod get_nv_capacity;
 pub mod get_nv_keycodes;
 pub mod get_nv_remaining_capacity;
@@ -33,6 +34,7 @@ pub fn all() -> Rc<Vec<Command>> {
         define_nv_graphics_raster::new(),
         define_download_graphics_raster::new(),
         get_download_keycodes::new(),
+        get_download_remaining_capacity::new(),
         get_nv_capacity::new(),
         get_nv_keycodes::new(),
         get_nv_remaining_capacity::new(),
@@ -46,3 +48,38 @@ pub fn all() -> Rc<Vec<Command>> {
 
     Rc::new(all)
 }
+
+//Header 37h, the identifier and the number of bytes as decimal text terminated by NUL
+pub fn capacity_response(identifier: u8, bytes: u32) -> Option<Vec<DeviceCommand>> {
+    let mut response = vec![0x37, identifier];
+    response.extend(bytes.to_string().as_bytes());
+    response.push(0x00);
+    Some(vec![DeviceCommand::Transmit(response)])
+}
+
+//Header 37h and the identifier followed by blocks of up to 80 key codes terminated by NUL,
+//the status is 41h when another block follows and 40h for the last one
+pub fn key_code_response(identifier: u8, key_codes: Vec<(u8, u8)>) -> Option<Vec<DeviceCommand>> {
+    let blocks = key_codes.chunks(80).collect::<Vec<&[(u8, u8)]>>();
+    let blocks = if blocks.is_empty() {
+        vec![&[][..]]
+    } else {
+        blocks
+    };
+    let last = blocks.len() - 1;
+
+    Some(
+        blocks
+            .iter()
+            .enumerate()
+            .map(|(i, block)| {
+                let mut response = vec![0x37, identifier, if i == last { 0x40 } else { 0x41 }];
+                for (kc1, kc2) in block.iter() {
+                    response.extend([*kc1, *kc2]);
+                }
+                response.push(0x00);
+                DeviceCommand::Transmit(response)
+            })
+            .collect(),
+    )
+}
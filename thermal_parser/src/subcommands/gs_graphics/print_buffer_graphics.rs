@@ -4,6 +4,11 @@ use crate::{command::*, context::*, graphics::*};
 pub struct Handler;
 
 impl CommandHandler for Handler {
+    fn apply_context(&self, _command: &Command, context: &mut Context) {
+        //Printing the graphic empties the print buffer
+        context.graphics.buffer_graphics = None;
+    }
+
     fn get_graphics(&self, _command: &Command, context: &Context) -> Option<GraphicsCommand> {
         return match &context.graphics.buffer_graphics {
             Some(img) => Some(GraphicsCommand::Image(img.clone())),
@@ -7,7 +7,13 @@ impl CommandHandler for Handler {
     fn get_graphics(&self, command: &Command, context: &Context) -> Option<GraphicsCommand> {
         if let Some(img_ref) = ImageRef::from_data(&command.data, ImageRefStorage::Ram) {
             if let Some(img) = context.graphics.stored_graphics.get(&img_ref) {
-                return Some(GraphicsCommand::Image(img.clone()));
+                //x and y select the horizontal and vertical expansion (1 or 2)
+                let mut img = img.clone();
+                img.stretch = (
+                    *command.data.get(2).unwrap_or(&1),
+                    *command.data.get(3).unwrap_or(&1),
+                );
+                return Some(GraphicsCommand::Image(img));
             }
         }
         None
@@ -5,8 +5,12 @@ pub struct Handler;
 
 impl CommandHandler for Handler {
     fn apply_context(&self, command: &Command, context: &mut Context) {
+        //Each color is stored with its own command
         if let Some(img) = Image::from_column_data(&command.data) {
-            context.graphics.buffer_graphics = Some(img)
+            context.graphics.buffer_graphics = match context.graphics.buffer_graphics.take() {
+                Some(buffered) => Some(buffered.with_plane(img)),
+                None => Some(img),
+            }
         }
     }
 }
@@ -1,5 +1,8 @@
+use std::cell::RefCell;
 use std::path::PathBuf;
-use thermal_parser::{command::Command, context::*};
+use std::rc::Rc;
+use std::sync::Arc;
+use thermal_parser::{command::*, context::*, graphics::*, nv_store::*, response::*};
 
 #[test]
 fn it_parses_column_format() {
@@ -36,6 +39,835 @@ fn it_parses_image_with_transmit() {
     test_binary_file("image_with_transmit.bin", true);
 }
 
+#[test]
+fn it_parses_page_mode_demo() {
+    test_binary_file("page_mode_demo.bin", true);
+}
+
+#[test]
+fn it_encodes_code39_with_start_and_stop_characters() {
+    //With and without the optional start and stop characters
+    let bytes = [
+        &[0x1D, b'k', 69, 5][..],
+        b"*AB1*",
+        &[0x1D, b'k', 69, 3],
+        b"AB1",
+        &[0x0A],
+    ]
+    .concat();
+    let barcodes = Rc::new(RefCell::new(Vec::new()));
+    let collected = barcodes.clone();
+    let context = Context::new();
+
+    let on_new_command = move |cmd: Command| {
+        if let Some(thermal_parser::graphics::GraphicsCommand::Barcode(barcode)) =
+            cmd.handler.get_graphics(&cmd, &context)
+        {
+            collected.borrow_mut().push(barcode.points);
+        }
+    };
+    let mut command_parser = thermal_parser::new_esc_pos_parser(Box::from(on_new_command));
+    command_parser.parse_bytes(&bytes);
+
+    let barcodes = barcodes.borrow();
+    assert_eq!(barcodes.len(), 2);
+    assert_eq!(barcodes[0], barcodes[1]);
+}
+
+#[test]
+fn it_converts_8_dot_bit_image_columns() {
+    //Two columns, the first with its top dot and the second with its bottom dot
+    let bytes = vec![0x1B, b'*', 0, 2, 0, 0x80, 0x01, 0x0A];
+    let images = Rc::new(RefCell::new(Vec::new()));
+    let collected = images.clone();
+    let context = Context::new();
+
+    let on_new_command = move |cmd: Command| {
+        if let Some(thermal_parser::graphics::GraphicsCommand::Image(img)) =
+            cmd.handler.get_graphics(&cmd, &context)
+        {
+            collected.borrow_mut().push(img.pixels);
+        }
+    };
+    let mut command_parser = thermal_parser::new_esc_pos_parser(Box::from(on_new_command));
+    command_parser.parse_bytes(&bytes);
+
+    let mut expected = vec![255u8; 16];
+    expected[0] = 0;
+    expected[15] = 0;
+    assert_eq!(images.borrow().clone(), vec![expected]);
+}
+
+#[test]
+fn it_parses_print_positions() {
+    let device_commands = collect_device_commands(&vec![
+        0x1B, b'$', 0x2C, 0x01, //absolute 300
+        0x1B, b'\\', 0xF6, 0xFF, //relative -10
+    ]);
+    assert!(device_commands[1] == DeviceCommand::MoveX(300));
+    assert!(device_commands[2] == DeviceCommand::MoveXRelative(-10));
+}
+
+#[test]
+fn it_applies_left_margin_and_print_area_width() {
+    let context = apply_commands(&vec![
+        0x1D, b'L', 40, 0, //left margin 40
+        0x1D, b'W', 0x2C, 0x01, //print area width 300
+    ]);
+    assert_eq!(context.print_area_left_pixels(), 40);
+    assert_eq!(context.available_width_pixels(), 300);
+    assert_eq!(context.graphics_x_offset(300), 40);
+
+    let context = apply_commands(&vec![0x1D, b'L', 40, 0, 0x1D, b'W', 0xFF, 0xFF]);
+    assert_eq!(
+        context.available_width_pixels(),
+        context.printable_width_pixels() - 40
+    );
+}
+
+#[test]
+fn it_parses_spacing_and_feeds() {
+    let device_commands = collect_device_commands(&vec![
+        0x1B, b'J', 20, //feed 20
+        0x1B, b'K', 20, //reverse feed 20
+        0x1B, b'd', 3, //feed 3 lines
+    ]);
+    assert!(device_commands[1] == DeviceCommand::Feed(20));
+    assert!(device_commands[2] == DeviceCommand::Feed(-20));
+    assert!(device_commands[3] == DeviceCommand::FeedLine(3));
+
+    let context = apply_commands(&vec![0x1B, b' ', 4, 0x1B, b'3', 50, 0x1D, b'!', 0x10]);
+    assert_eq!(context.char_spacing_pixels(), 8);
+    assert_eq!(context.line_height_pixels(), 50);
+}
+
+#[test]
+fn it_applies_justification_only_at_line_start() {
+    let mut context = Context::new();
+    context.emulate_line_buffer = true;
+
+    //Text fills the line buffer, right justification is ignored mid-line
+    let context = apply_commands_in(
+        context,
+        &vec![0x1B, b'a', 1, b'a', b'b', 0x1B, b'a', 2, b'c'],
+    );
+    assert!(!context.line_is_empty);
+    assert!(context.text.justify == TextJustify::Center);
+
+    //The line feed prints the line
+    let context = apply_commands_in(context, &vec![0x0A, 0x1B, b'a', 2, b'c']);
+    assert!(context.text.justify == TextJustify::Right);
+
+    //So does printing a graphic
+    let raster = [0x1D, b'v', b'0', 0, 1, 0, 1, 0, 0x80];
+    let context = apply_commands_in(context, &[&raster[..], &[0x1B, b'a', 0, b'c']].concat());
+    assert!(context.text.justify == TextJustify::Left);
+}
+
+#[test]
+fn it_parses_page_mode() {
+    let device_commands = collect_device_commands(&vec![
+        0x0C, //form feed is ignored in standard mode
+        0x1B, b'L', //page mode
+        b'P', 0x18, //cancel the page
+        0x1B, 0x0C, //print the page
+        0x1B, b'S', //standard mode
+        0x18, 0x0C, //ignored in standard mode
+    ]);
+    assert!(device_commands[1] == DeviceCommand::BeginPage);
+    assert!(device_commands[2] == DeviceCommand::Cancel);
+    assert!(device_commands[3] == DeviceCommand::PrintPage);
+    assert!(device_commands[4] == DeviceCommand::EndPage);
+    assert!(device_commands[5] == DeviceCommand::EndPrint);
+
+    let device_commands = collect_device_commands(&vec![0x1B, b'L', 0x0C]);
+    assert!(device_commands[2] == DeviceCommand::PrintPage);
+    assert!(device_commands[3] == DeviceCommand::EndPage);
+
+    let context = apply_commands(&vec![0x1B, b'L', 0x1B, b'@']);
+    assert!(!context.is_page_mode);
+
+    //Form feed prints the page and returns to standard mode
+    let context = apply_commands(&vec![0x1B, b'L', 0x0C, 0x0A]);
+    assert!(!context.is_page_mode);
+}
+
+#[test]
+fn it_applies_page_area_and_direction() {
+    let context = apply_commands(&vec![
+        0x1B, b'L', //page mode
+        0x1B, b'W', 10, 0, 20, 0, 0x2C, 0x01, 0xC8, 0x00, //area 10, 20, 300 x 200
+        0x1B, b'T', 1, //bottom to top
+    ]);
+    assert!(
+        context.page_area()
+            == PageArea {
+                x: 10,
+                y: 20,
+                width: 300,
+                height: 200
+            }
+    );
+    assert!(context.graphics.page_direction == PrintDirection::BottomToTop);
+    assert_eq!(context.page_area_logical_size(), (200, 300));
+    assert_eq!(context.available_width_pixels(), 200);
+
+    //Without an area the page is as long as the ESC W default, at the print resolution
+    let context = apply_commands(&vec![0x1B, b'L', 0x0A]);
+    assert_eq!(context.page_area().height, 969);
+
+    //Areas past the printable width are cut off
+    let context = apply_commands(&vec![0x1B, b'W', 0, 0, 0, 0, 0xFF, 0xFF, 10, 0]);
+    assert_eq!(context.page_area().width, context.printable_width_pixels());
+
+    let device_commands = collect_device_commands(&vec![
+        0x1D, b'$', 40, 0, //ignored in standard mode
+        0x1B, b'L', //page mode
+        0x1D, b'$', 40, 0, //absolute 40
+        0x1D, b'\\', 0xF6, 0xFF, //relative -10
+    ]);
+    assert!(device_commands[1] == DeviceCommand::BeginPage);
+    assert!(device_commands[2] == DeviceCommand::MoveY(40));
+    assert!(device_commands[3] == DeviceCommand::MoveYRelative(-10));
+}
+
+#[test]
+fn it_applies_rotation_in_standard_mode_only() {
+    let context = apply_commands(&vec![0x1B, b'V', b'1']);
+    assert!(context.text.rotation == TextRotation::OneDotSpacing);
+    assert!(context.is_rotated());
+    assert_eq!(context.rotation_spacing_pixels(), 1);
+
+    //Rotation is kept but has no effect in page mode
+    let context = apply_commands(&vec![0x1B, b'V', 2, 0x1B, b'L']);
+    assert!(context.text.rotation == TextRotation::OneAndHalfDotSpacing);
+    assert!(!context.is_rotated());
+    assert_eq!(context.rotation_spacing_pixels(), 0);
+}
+
+#[test]
+fn it_applies_upside_down_in_standard_mode_only() {
+    let context = apply_commands(&vec![0x1B, b'{', 1]);
+    assert!(context.is_upside_down());
+
+    let context = apply_commands(&vec![0x1B, b'{', 1, 0x1B, b'L']);
+    assert!(!context.is_upside_down());
+}
+
+#[test]
+fn it_applies_character_sizes() {
+    for n in 0..8u8 {
+        for m in 0..8u8 {
+            let context = apply_commands(&vec![0x1D, b'!', (n << 4) | m]);
+            assert_eq!(context.text.width_mult, n + 1);
+            assert_eq!(context.text.height_mult, m + 1);
+        }
+    }
+}
+
+#[test]
+fn it_applies_double_strike_without_strikethrough() {
+    let context = apply_commands(&vec![0x1B, b'G', 1]);
+    assert!(context.text.double_strike);
+    assert!(context.text.strikethrough == TextStrikethrough::Off);
+
+    let context = apply_commands(&vec![0x1B, b'G', 1, 0x1B, b'G', 0]);
+    assert!(!context.text.double_strike);
+
+    //Strikethrough is only set from code and outlasts ESC @
+    let mut context = Context::new();
+    context.set_strikethrough(TextStrikethrough::Double);
+    let context = apply_commands_in(context, &vec![0x1B, b'@', 0x1B, b'G', 1]);
+    assert!(context.text.strikethrough == TextStrikethrough::Double);
+}
+
+#[test]
+fn it_applies_character_colors() {
+    let context = apply_commands(&vec![0x1B, b'r', 1]);
+    assert_eq!(context.text.color, Color::Red);
+
+    let context = apply_commands(&vec![
+        0x1D, b'(', b'N', 2, 0, 48, 48, //no character color
+        0x1D, b'(', b'N', 2, 0, 49, 50, //background color 2
+        0x1D, b'(', b'N', 3, 0, 50, 49, 49, //shading with color 1
+    ]);
+    assert_eq!(context.text.color, Color::None);
+    assert_eq!(context.text.background_color, Color::Red);
+    assert_eq!(context.text.shadow_color, Color::Black);
+
+    //Color 3 is ignored, shading can be turned off
+    let context = apply_commands(&vec![
+        0x1D, b'(', b'N', 2, 0, 48, 51, //
+        0x1D, b'(', b'N', 3, 0, 50, 48, 49, //
+    ]);
+    assert_eq!(context.text.color, Color::Black);
+    assert_eq!(context.text.shadow_color, Color::None);
+}
+
+#[test]
+fn it_combines_graphics_planes() {
+    //GS ( L fn 112 storing a single 4x1 plane of tone a and color c
+    let plane = |a: u8, c: u8, bits: u8| {
+        vec![
+            0x1D, b'(', b'L', 11, 0, 48, 112, a, 1, 1, c, 4, 0, 1, 0, bits,
+        ]
+    };
+
+    //Two tone planes, color 1 is the most significant bit
+    //Subcommands complete on the following byte, hence the trailing LF
+    let context = apply_commands(
+        &[
+            plane(52, 49, 0b1100_0000),
+            plane(52, 50, 0b1010_0000),
+            vec![0x0A],
+        ]
+        .concat(),
+    );
+    let image = context.graphics.buffer_graphics.unwrap();
+    assert_eq!(image.as_grayscale(), vec![0, 85, 170, 255]);
+    assert!(image.as_colors().is_none());
+
+    //Two color monochrome planes, color 1 wins where both are set
+    let context = apply_commands(
+        &[
+            plane(48, 49, 0b1100_0000),
+            plane(48, 50, 0b1010_0000),
+            vec![0x0A],
+        ]
+        .concat(),
+    );
+    let image = context.graphics.buffer_graphics.unwrap();
+    assert_eq!(image.as_grayscale(), vec![0, 0, 0, 255]);
+    assert_eq!(
+        image.as_colors().unwrap()[0..3],
+        [Color::Black, Color::Black, Color::Red]
+    );
+}
+
+#[test]
+fn it_clears_buffer_graphics_once_printed() {
+    let plane = |bits: u8| {
+        vec![
+            0x1D, b'(', b'L', 11, 0, 48, 112, 48, 1, 1, 49, 4, 0, 1, 0, bits,
+        ]
+    };
+    let print = vec![0x1D, b'(', b'L', 2, 0, 48, 50];
+
+    //The second graphic doesn't merge into the printed one
+    let images = collect_images(
+        &[
+            plane(0b1100_0000),
+            print.clone(),
+            plane(0b0011_0000),
+            print,
+            vec![0x0A],
+        ]
+        .concat(),
+    );
+    assert_eq!(images.len(), 2);
+    assert_eq!(images[0].as_grayscale(), vec![0, 0, 255, 255]);
+    assert_eq!(images[1].as_grayscale(), vec![255, 255, 0, 0]);
+}
+
+#[test]
+fn it_applies_image_stretch() {
+    let stretches = collect_images(&vec![
+        0x1D, b'v', b'0', 3, 1, 0, 1, 0, 0xFF, //quadruple
+        0x1D, b'v', b'0', 51, 1, 0, 1, 0, 0xFF, //quadruple
+        0x1B, b'*', 0, 1, 0, 0xFF, //8 dot single density
+        0x1B, b'*', 33, 1, 0, 0xFF, 0xFF, 0xFF, //24 dot double density
+        0x0A,
+    ])
+    .iter()
+    .map(|image| (image.stretch, image.stretched_size()))
+    .collect::<Vec<_>>();
+
+    assert_eq!(
+        stretches,
+        vec![
+            ((2, 2), (16, 2)),
+            ((2, 2), (16, 2)),
+            ((2, 3), (2, 24)),
+            ((1, 1), (1, 24)),
+        ]
+    );
+}
+
+#[test]
+fn it_defines_bmp_graphics() {
+    //4x2 1 bit bmp with a black and white palette, rows are stored bottom up
+    let mut bmp = vec![b'B', b'M'];
+    bmp.extend(70u32.to_le_bytes());
+    bmp.extend(0u32.to_le_bytes());
+    bmp.extend(62u32.to_le_bytes());
+    for value in [40u32, 4, 2] {
+        bmp.extend(value.to_le_bytes());
+    }
+    bmp.extend([1, 0, 1, 0]);
+    bmp.extend([0u8; 24]);
+    bmp.extend([0, 0, 0, 0, 255, 255, 255, 0]);
+    bmp.extend([0b1111_0000, 0, 0, 0, 0b0101_0000, 0, 0, 0]);
+
+    //Define as NV graphics then print, define as download graphics with tones then print
+    let bytes = [
+        vec![0x1D, b'D', 48, 67, 48, 32, 33, 48, 49],
+        bmp.clone(),
+        vec![0x1D, b'(', b'L', 6, 0, 48, 69, 32, 33, 1, 1],
+        vec![0x1D, b'D', 48, 83, 48, 32, 33, 52, 49],
+        bmp,
+        vec![0x1D, b'(', b'L', 6, 0, 48, 85, 32, 33, 2, 1],
+        vec![0x0A],
+    ]
+    .concat();
+
+    let images = collect_images(&bytes);
+    assert_eq!(images.len(), 2);
+    for image in &images {
+        assert_eq!((image.width, image.height), (4, 2));
+        assert_eq!(
+            image.as_grayscale(),
+            vec![0, 255, 0, 255, 255, 255, 255, 255]
+        );
+    }
+    assert_eq!(images[1].stretch, (2, 1));
+}
+
+#[test]
+fn it_decodes_4_and_8_bit_bmp_palettes() {
+    //3x1 4 bit bmp using the third, first and second palette entries
+    let palette = [[0, 0, 0], [255, 255, 255], [128, 128, 128]];
+    let bmp = bmp_file(3, 1, 4, &palette, &[0x20, 0x10, 0, 0]);
+    let image = Image::from_bmp(&bmp, 52, 49).unwrap();
+    assert_eq!((image.width, image.height), (3, 1));
+    assert_eq!(image.as_grayscale(), vec![128, 0, 255]);
+
+    //2x2 8 bit bmp with a gray ramp palette, rows are stored bottom up
+    let palette = (0..=255).map(|i| [i, i, i]).collect::<Vec<_>>();
+    let bmp = bmp_file(2, 2, 8, &palette, &[10, 20, 0, 0, 30, 40, 0, 0]);
+    let image = Image::from_bmp(&bmp, 52, 49).unwrap();
+    assert_eq!(image.as_grayscale(), vec![30, 40, 10, 20]);
+
+    //Printed in color the dots darker than half are set
+    let image = Image::from_bmp(&bmp, 48, 49).unwrap();
+    assert_eq!(image.as_grayscale(), vec![0, 0, 0, 0]);
+}
+
+#[test]
+fn it_rejects_bmp_headers_larger_than_the_data() {
+    //A 70 byte bmp claiming 0x7FFFFFFF x 0x7FFFFFFF dots
+    let rows = [0x80, 0, 0, 0, 0x80, 0, 0, 0];
+    let mut bmp = bmp_file(1, 2, 1, &[[0, 0, 0], [255, 255, 255]], &rows);
+    bmp[18..22].copy_from_slice(&0x7FFFFFFFu32.to_le_bytes());
+    bmp[22..26].copy_from_slice(&0x7FFFFFFFu32.to_le_bytes());
+    assert_eq!(bmp.len(), 70);
+    assert!(Image::from_bmp(&bmp, 52, 49).is_none());
+
+    //A corrupt file size doesn't swallow the commands that follow
+    bmp[2..6].copy_from_slice(&0xFFFFFFF0u32.to_le_bytes());
+    let bytes = [
+        vec![0x1D, b'D', 48, 67, 48, 32, 33, 48, 49],
+        bmp,
+        vec![0x1D, b'Q', b'0', 0, 1, 0, 1, 0, 0xFF, 0x0A],
+    ]
+    .concat();
+    assert_eq!(collect_images(&bytes).len(), 1);
+}
+
+#[test]
+fn it_prints_legacy_bit_images() {
+    //8x8 column data with only the first column printed
+    let column = vec![0xFF, 0, 0, 0, 0, 0, 0, 0];
+    let bytes = [
+        vec![0x1D, b'*', 1, 1],
+        column.clone(),
+        vec![0x1D, b'/', 1], //double width
+        vec![0x1C, b'q', 2, 1, 0, 1, 0],
+        vec![0; 8],
+        vec![1, 0, 1, 0],
+        column.clone(),
+        vec![0x1C, b'p', 2, 51], //second image, quadruple
+        vec![0x1C, b'p', 3, 0],  //not defined
+        vec![0x1D, b'Q', b'0', 2, 2, 0, 1, 0, 0xFF, 0], //double height
+        vec![0x0A],
+    ]
+    .concat();
+
+    let images = collect_images(&bytes);
+    let sizes = images
+        .iter()
+        .map(|image| (image.stretched_size(), image.as_grayscale()[0..2].to_vec()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        sizes,
+        vec![
+            ((16, 8), vec![0, 255]),
+            ((16, 16), vec![0, 255]),
+            ((2, 16), vec![0, 255]),
+        ]
+    );
+}
+
+//Each stream ends after the first column, the parser emits the partial command
+#[test]
+fn it_leaves_truncated_downloaded_bit_image_columns_white() {
+    let (context, _) = collect_images_in(Context::new(), &vec![0x1D, b'*', 1, 1, 0xFF]);
+    let image = context.graphics.download_bit_image.unwrap();
+    assert_eq!((image.pixels.len(), black_dots(&image)), (64, 8));
+}
+
+#[test]
+fn it_leaves_truncated_nv_bit_image_columns_white() {
+    let (context, _) = collect_images_in(Context::new(), &vec![0x1C, b'q', 1, 1, 0, 1, 0, 0xFF]);
+    let image = &context.graphics.nv_bit_images[0];
+    assert_eq!((image.pixels.len(), black_dots(image)), (64, 8));
+}
+
+#[test]
+fn it_leaves_truncated_variable_bit_image_columns_white() {
+    let images = collect_images(&vec![0x1D, b'Q', b'0', 0, 2, 0, 1, 0, 0xFF]);
+    assert_eq!((images[0].pixels.len(), black_dots(&images[0])), (16, 8));
+}
+
+#[test]
+fn it_keeps_nv_graphics_in_store() {
+    let dir = std::env::temp_dir().join(format!("thermal_parser_nv_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let _ = std::fs::remove_file(&dir);
+    let session = |bytes: Vec<u8>| {
+        let mut context = Context::new();
+        context
+            .set_nv_store(Arc::new(DirectoryNvStore::new(&dir)))
+            .unwrap();
+        collect_images_in(context, &[bytes, vec![0x0A]].concat())
+    };
+
+    //An 8x1 NV graphic with key codes "AB" survives a reset
+    let (context, _) = session(vec![
+        0x1D, b'(', b'L', 12, 0, 48, 67, 48, b'A', b'B', 1, 8, 0, 1, 0, 49, 0xF0, //
+        0x1B, b'@',
+    ]);
+    assert_eq!(context.graphics.stored_graphics.len(), 1);
+
+    //And is printed in a later session
+    let (_, images) = session(vec![0x1D, b'(', b'L', 6, 0, 48, 69, b'A', b'B', 1, 1]);
+    assert_eq!(images.len(), 1);
+    assert_eq!(
+        images[0].as_grayscale(),
+        vec![0, 0, 0, 0, 255, 255, 255, 255]
+    );
+
+    //Until all NV graphics are cleared
+    session(vec![0x1D, b'(', b'L', 5, 0, 48, 65, b'C', b'L', b'R']);
+    let (context, images) = session(vec![0x1D, b'(', b'L', 6, 0, 48, 69, b'A', b'B', 1, 1]);
+    assert!(context.graphics.stored_graphics.is_empty());
+    assert!(images.is_empty());
+
+    //Update errors are kept on the context, the graphic is still defined for this session
+    std::fs::remove_dir_all(&dir).unwrap();
+    std::fs::write(&dir, b"").unwrap();
+    let mut context = Context::new();
+    context.graphics.nv_store = Some(Arc::new(DirectoryNvStore::new(dir.join("nv"))));
+    let (context, _) = collect_images_in(
+        context,
+        &vec![
+            0x1D, b'(', b'L', 12, 0, 48, 67, 48, b'A', b'B', 1, 8, 0, 1, 0, 49, 0xF0, //
+            0x1B, b'@', 0x0A,
+        ],
+    );
+    assert!(context.graphics.nv_store_error.is_some());
+    assert_eq!(context.graphics.stored_graphics.len(), 1);
+
+    //Load errors are returned
+    let mut context = Context::new();
+    assert!(context
+        .set_nv_store(Arc::new(DirectoryNvStore::new(&dir)))
+        .is_err());
+
+    let _ = std::fs::remove_file(&dir);
+}
+
+#[test]
+fn it_transmits_graphics_capacity_and_key_codes() {
+    let transmitted = collect_transmitted(&vec![
+        0x1D, b'(', b'L', 12, 0, 48, 67, 48, b'A', b'B', 1, 8, 0, 1, 0, 49, 0xF0, //NV "AB"
+        0x1D, b'(', b'L', 12, 0, 48, 83, 48, b'C', b'D', 1, 8, 0, 1, 0, 49,
+        0xF0, //download "CD"
+        0x1D, b'(', b'L', 2, 0, 48, 48, //NV capacity
+        0x1D, b'(', b'L', 2, 0, 48, 51, //NV remaining capacity
+        0x1D, b'(', b'L', 2, 0, 48, 52, //download remaining capacity
+        0x1D, b'(', b'L', 4, 0, 48, 64, b'K', b'C', //NV key codes
+        0x1D, b'(', b'L', 4, 0, 48, 80, b'K', b'C', //download key codes
+        0x0A,
+    ]);
+
+    assert_eq!(
+        transmitted,
+        vec![
+            [&[0x37, 0x30][..], b"262144", &[0]].concat(),
+            [&[0x37, 0x31][..], b"262143", &[0]].concat(),
+            [&[0x37, 0x32][..], b"65535", &[0]].concat(),
+            vec![0x37, 0x72, 0x40, b'A', b'B', 0],
+            vec![0x37, 0x76, 0x40, b'C', b'D', 0],
+        ]
+    );
+}
+
+#[test]
+fn it_transmits_symbol_size() {
+    let transmitted = collect_transmitted(&vec![
+        0x1D, b'(', b'k', 3, 0, 49, 82, 48, //nothing stored
+        0x1D, b'(', b'k', 3, 0, 49, 67, 3, //module size 3
+        0x1D, b'(', b'k', 5, 0, 49, 80, 48, b'h', b'i', //store
+        0x1D, b'(', b'k', 3, 0, 49, 82, 48, //17 modules of the default micro QR version 4
+        0x0A,
+    ]);
+
+    assert_eq!(
+        transmitted,
+        vec![
+            vec![0x37, 0x36, b'0', 0x1F, b'0', 0x1F, 0x31, 0],
+            [&[0x37, 0x36][..], b"51", &[0x1F], b"51", &[0x1F, 0x30, 0]].concat(),
+        ]
+    );
+}
+
+#[test]
+fn it_transmits_printer_identity() {
+    let transmitted = collect_transmitted(&vec![
+        0x1D, b'I', 1, //model id
+        0x1D, b'I', 67, //model name
+        0x1D, b'I', 69, //fonts of languages
+        0x1D, b'(', b'H', 6, 0, 48, 48, b'1', b'2', b'3', b'4', //process id
+        0x1D, b'(', b'E', 3, 0, 1, b'I', b'N', //user setting mode
+        0x1D, b'(', b'E', 10, 0, 3, 2, b'0', b'1', b'2', b'2', b'2', b'2', b'2', b'1', //msw 2
+        0x1D, b'(', b'E', 2, 0, 4, 2, //transmit msw 2
+        0x0A,
+    ]);
+
+    assert_eq!(
+        transmitted,
+        vec![
+            vec![0x20],
+            [&[0x5F][..], b"TM-T88V", &[0]].concat(),
+            vec![0x5F, 0],
+            vec![0x37, 0x22, b'1', b'2', b'3', b'4', 0],
+            vec![0x37, 0x20, 0],
+            [&[0x37, 0x21][..], b"01000001", &[0]].concat(),
+        ]
+    );
+}
+
+#[test]
+fn it_processes_real_time_commands_out_of_band() {
+    let bytes = vec![
+        0x10, 0x04, 1, //printer status
+        0x1D, b'v', b'0', 0, 1, 0, 3, 0, 0x10, 0x04, 2, //within the data of a bit image
+        0x10, 0x14, 8, 1, 3, 20, 1, 6, 2, 8, //clear buffers
+        0x10, 0x14, 2, 1, 8, //power off
+        0x0A,
+    ];
+    let transmitted = collect_transmitted(&bytes);
+    assert_eq!(
+        transmitted,
+        vec![
+            vec![0x12],
+            vec![0x12],
+            vec![0x37, 0x25, 0x00],
+            vec![0x3B, 0x30, 0x00],
+        ]
+    );
+
+    //The bit image keeps its data
+    let images = collect_images(&bytes);
+    assert_eq!(images[0].pixels, vec![0x10, 0x04, 2]);
+
+    let state = PrinterState {
+        cover_open: true,
+        paper_near_end: true,
+        ..Default::default()
+    };
+    assert_eq!(state.status_byte(1), Some(0x1A));
+    assert_eq!(state.status_byte(2), Some(0x16));
+    assert_eq!(state.status_byte(4), Some(0x1E));
+}
+
+#[test]
+fn it_parses_drawer_pulses() {
+    let bytes = vec![
+        0x1B, b'p', 48, 50, 20, //off time is never shorter than on time
+        0x10, 0x14, 1, 1, 3, //real-time pulse
+        0x10, 0x14, 1, 0, 9, //out of range
+        0x0A,
+    ];
+    let pulses = collect_device_commands(&bytes)
+        .into_iter()
+        .filter(|command| matches!(command, DeviceCommand::Pulse { .. }))
+        .collect::<Vec<DeviceCommand>>();
+    assert_eq!(
+        pulses,
+        vec![
+            DeviceCommand::Pulse {
+                pin: 2,
+                on_ms: 100,
+                off_ms: 100
+            },
+            DeviceCommand::Pulse {
+                pin: 5,
+                on_ms: 300,
+                off_ms: 300
+            },
+        ]
+    );
+}
+
+#[test]
+fn it_sends_automatic_status_back() {
+    let bytes = vec![
+        0x1D, b'a', 0b1001, //drawer and paper sensor
+        0x1D, b'r', 1, //paper sensor
+        0x1D, b'r', 50, //drawer
+        0x0A,
+    ];
+    let transmitted = collect_transmitted(&bytes);
+    assert_eq!(
+        transmitted,
+        vec![vec![0x10, 0x00, 0x00, 0x00], vec![0x00], vec![0x00]]
+    );
+
+    let mut context = apply_commands(&bytes);
+    let responses = Arc::new(ResponseBuffer::new());
+    context.response_sink = Some(responses.clone());
+
+    //Errors are not enabled
+    context.set_printer_state(PrinterState {
+        recoverable_error: true,
+        ..context.printer_state.clone()
+    });
+    assert!(responses.take().is_empty());
+
+    context.set_printer_state(PrinterState {
+        paper_near_end: true,
+        ..context.printer_state.clone()
+    });
+    assert_eq!(responses.take(), vec![0x18, 0x04, 0x03, 0x00]);
+    assert_eq!(context.printer_state.transmit_status_byte(1), Some(0x03));
+}
+
+#[test]
+fn it_sends_the_context_to_other_threads() {
+    let mut context = Context::new();
+    context.response_sink = Some(Arc::new(ResponseBuffer::new()));
+    let context = std::thread::spawn(move || context).join().unwrap();
+    assert!(context.response_sink.is_some());
+}
+
+fn apply_commands(bytes: &Vec<u8>) -> Context {
+    apply_commands_in(Context::new(), bytes)
+}
+
+fn apply_commands_in(context: Context, bytes: &Vec<u8>) -> Context {
+    let context = Rc::new(RefCell::new(context));
+    let applied = context.clone();
+
+    let on_new_command = move |cmd: Command| match cmd.kind {
+        CommandType::Graphics => cmd.apply_graphics(&mut applied.borrow_mut()),
+        _ => cmd.handler.apply_context(&cmd, &mut applied.borrow_mut()),
+    };
+    let mut command_parser = thermal_parser::new_esc_pos_parser(Box::from(on_new_command));
+    command_parser.parse_bytes(bytes);
+
+    let result = context.borrow().clone();
+    result
+}
+
+fn collect_device_commands(bytes: &Vec<u8>) -> Vec<DeviceCommand> {
+    let device_commands = Rc::new(RefCell::new(Vec::<DeviceCommand>::new()));
+    let collected = device_commands.clone();
+    let mut context = Context::new();
+
+    let on_new_command = move |cmd: Command| {
+        let taken_first = matches!(cmd.kind, CommandType::Control | CommandType::RealTime);
+        if !taken_first {
+            cmd.handler.apply_context(&cmd, &mut context);
+        }
+        if let Some(found) = cmd.handler.get_device_command(&cmd, &context) {
+            collected.borrow_mut().extend(found);
+        }
+        if taken_first {
+            cmd.handler.apply_context(&cmd, &mut context);
+        }
+    };
+    let mut command_parser = thermal_parser::new_esc_pos_parser(Box::from(on_new_command));
+    command_parser.parse_bytes(bytes);
+
+    let result = device_commands.borrow().clone();
+    result
+}
+
+//Bytes of the replies sent back to the host
+fn collect_transmitted(bytes: &Vec<u8>) -> Vec<Vec<u8>> {
+    collect_device_commands(bytes)
+        .into_iter()
+        .filter_map(|command| match command {
+            DeviceCommand::Transmit(bytes) => Some(bytes),
+            _ => None,
+        })
+        .collect()
+}
+
+//Windows bmp file with a 40 byte info header, palette colors are blue, green, red
+fn bmp_file(width: i32, height: i32, bit_count: u16, palette: &[[u8; 3]], rows: &[u8]) -> Vec<u8> {
+    let offset = 54 + palette.len() * 4;
+    let mut bmp = vec![b'B', b'M'];
+    bmp.extend(((offset + rows.len()) as u32).to_le_bytes());
+    bmp.extend(0u32.to_le_bytes());
+    bmp.extend((offset as u32).to_le_bytes());
+    bmp.extend(40u32.to_le_bytes());
+    bmp.extend(width.to_le_bytes());
+    bmp.extend(height.to_le_bytes());
+    bmp.extend(1u16.to_le_bytes());
+    bmp.extend(bit_count.to_le_bytes());
+    bmp.extend([0u8; 16]); //no compression, image size and resolution
+    bmp.extend((palette.len() as u32).to_le_bytes());
+    bmp.extend(0u32.to_le_bytes());
+    for [blue, green, red] in palette {
+        bmp.extend([*blue, *green, *red, 0]);
+    }
+    bmp.extend(rows);
+    bmp
+}
+
+fn black_dots(image: &Image) -> usize {
+    image.pixels.iter().filter(|pixel| **pixel == 0).count()
+}
+
+fn collect_images(bytes: &Vec<u8>) -> Vec<Image> {
+    collect_images_in(Context::new(), bytes).1
+}
+
+fn collect_images_in(context: Context, bytes: &Vec<u8>) -> (Context, Vec<Image>) {
+    let images = Rc::new(RefCell::new(Vec::<Image>::new()));
+    let context = Rc::new(RefCell::new(context));
+    let (collected, applied) = (images.clone(), context.clone());
+
+    let on_new_command = move |cmd: Command| {
+        let mut context = applied.borrow_mut();
+        //Graphics are printed before the command is applied, like in the renderer
+        if let Some(GraphicsCommand::Image(image)) = cmd.handler.get_graphics(&cmd, &context) {
+            collected.borrow_mut().push(image);
+        }
+        match cmd.kind {
+            CommandType::Graphics => cmd.apply_graphics(&mut context),
+            _ => cmd.handler.apply_context(&cmd, &mut context),
+        }
+    };
+    let mut command_parser = thermal_parser::new_esc_pos_parser(Box::from(on_new_command));
+    command_parser.parse_bytes(bytes);
+
+    let result = (context.borrow().clone(), images.borrow().clone());
+    result
+}
+
 fn test_binary_file(filename: &str, debug: bool) {
     let bytes = std::fs::read(get_test_bin(filename)).unwrap();
     let context = Context::new();
@@ -1,26 +1,44 @@
-use crate::renderer::CommandRenderer;
+use crate::renderer::{feed_pixels, CommandRenderer};
 use base64::engine::general_purpose;
 use base64::Engine;
 use png::{ColorType, Encoder};
 use std::path::PathBuf;
-use thermal_parser::command::DeviceCommand;
-use thermal_parser::context::{Context, TextJustify, TextStrikethrough, TextUnderline};
+use thermal_parser::command::{Command, DeviceCommand};
+use thermal_parser::context::{
+    Color, Context, PageArea, PrintDirection, TextJustify, TextStrikethrough, TextUnderline,
+};
 
 pub struct HtmlRenderer {
     pub out_path: String,
     pub content: Vec<String>,
     pub template: String,
+    pub second_ink: [u8; 3], //rgb of the second color (ESC r, GS ( N)
     pub font_size_pixels: usize,
     pub receipt_width_pixels: usize,
     pub receipt_margin_left_pixels: usize,
     pub receipt_margin_right_pixels: usize,
     pub pixel_scale_ratio: f32,
     pub current_justify: TextJustify,
+    pub current_print_area: (u32, u32),
+    pub current_line_height: u32,
     pub gfx_x: usize,
     pub gfx_y: usize,
     pub gfx_w: usize,
     pub gfx_h: usize,
     pub gfx_svg: Vec<String>,
+    //Position within the current line as css pixels plus monospace characters
+    pub line_x_pixels: f32,
+    pub line_x_chars: usize,
+    pub upside_down_line: bool, //an upside-down line block is open
+    //While in page mode the paper is set aside and the page is composed in content,
+    //each print area is a region positioned and rotated along its print direction
+    pub paper: Option<Vec<String>>,
+    pub paper_y: usize,
+    pub page_region: Option<(PageArea, PrintDirection)>,
+    pub page_region_start: usize,
+    pub page_region_y: usize, //furthest position reached in the region
+    pub page_height: u32,
+    pub held_commands: Vec<Command>,
 }
 
 impl HtmlRenderer {
@@ -34,17 +52,30 @@ impl HtmlRenderer {
             out_path,
             content: vec![],
             template: std::fs::read_to_string(template_path).unwrap(),
+            second_ink: [200, 16, 32],
             font_size_pixels: 0,
             receipt_width_pixels: 0,
             receipt_margin_left_pixels: 0,
             receipt_margin_right_pixels: 0,
             pixel_scale_ratio: 0.0,
             current_justify: TextJustify::Left,
+            current_print_area: (0, 0),
+            current_line_height: 0,
             gfx_x: 0,
             gfx_y: 0,
             gfx_w: 0,
             gfx_h: 0,
             gfx_svg: vec![],
+            line_x_pixels: 0.0,
+            line_x_chars: 0,
+            upside_down_line: false,
+            paper: None,
+            paper_y: 0,
+            page_region: None,
+            page_region_start: 0,
+            page_region_y: 0,
+            page_height: 0,
+            held_commands: vec![],
         }
     }
 }
@@ -57,11 +88,12 @@ impl CommandRenderer for HtmlRenderer {
         self.font_size_pixels = (context.text.font_size as f32 * 1.63) as usize;
         self.receipt_width_pixels = (context.graphics.paper_width * dpi) as usize;
         self.receipt_margin_left_pixels = (context.graphics.margin_left * dpi) as usize;
-        self.receipt_margin_right_pixels = (context.graphics.margin_left * dpi) as usize;
+        self.receipt_margin_right_pixels = (context.graphics.margin_right * dpi) as usize;
         self.start_container(context);
     }
 
     fn begin_graphics(&mut self, context: &mut Context) {
+        self.maybe_start_region(context);
         self.end_container();
         self.start_container(context);
         self.gfx_x = context.graphics.x;
@@ -86,33 +118,69 @@ impl CommandRenderer for HtmlRenderer {
     fn end_graphics(&mut self, context: &mut Context) {
         self.maybe_start_container(context);
 
-        self.content.push(format!(
+        let svg = format!(
             "<svg width='{}' height='{}'>{}</svg>",
             self.gfx_w,
             self.gfx_h,
             self.gfx_svg.join("\n")
-        ));
+        );
+        self.content.push(upside_down_block(context, svg));
 
         self.end_container();
         self.start_container(context);
     }
 
     fn draw_image(&mut self, context: &mut Context, bytes: Vec<u8>, width: usize, height: usize) {
+        self.maybe_start_region(context);
         self.end_container();
         self.start_container(context);
 
-        self.content
-            .push(self.encode_html_image(bytes, width as u32, height as u32));
+        let image =
+            self.encode_html_image(bytes, width as u32, height as u32, ColorType::Grayscale);
+        self.content.push(upside_down_block(context, image));
+
+        self.end_container();
+        self.start_container(context);
+    }
+
+    fn draw_color_image(
+        &mut self,
+        context: &mut Context,
+        bytes: Vec<u8>,
+        colors: Vec<Color>,
+        width: usize,
+        height: usize,
+    ) {
+        self.maybe_start_region(context);
+        self.end_container();
+        self.start_container(context);
+
+        let mut rgb = Vec::<u8>::with_capacity(bytes.len() * 3);
+        for (pixel, color) in bytes.iter().zip(&colors) {
+            match color {
+                Color::Red => {
+                    let darkness = (255 - *pixel) as f32 / 255.0;
+                    for channel in self.second_ink {
+                        rgb.push((255.0 - darkness * (255 - channel) as f32) as u8);
+                    }
+                }
+                _ => rgb.extend_from_slice(&[*pixel; 3]),
+            }
+        }
+
+        let image = self.encode_html_image(rgb, width as u32, height as u32, ColorType::Rgb);
+        self.content.push(upside_down_block(context, image));
 
         self.end_container();
         self.start_container(context);
     }
 
     fn draw_text(&mut self, context: &mut Context, text: String) {
+        self.maybe_start_region(context);
         self.maybe_start_container(context);
         let mut class_list = vec![];
 
-        if context.text.bold {
+        if context.text.bold || context.text.double_strike {
             class_list.push("b");
         }
 
@@ -128,16 +196,26 @@ impl CommandRenderer for HtmlRenderer {
             class_list.push("sd");
         }
 
-        if context.text.width_mult == 2 {
-            class_list.push("dw");
+        if context.text.invert == true {
+            class_list.push("in");
         }
 
-        if context.text.height_mult == 2 {
-            class_list.push("dh");
+        match context.text.color {
+            Color::None => class_list.push("c0"),
+            Color::Red => class_list.push("c2"),
+            Color::Black => {}
         }
 
-        if context.text.invert == true {
-            class_list.push("in");
+        match context.text.background_color {
+            Color::Black => class_list.push("bg1"),
+            Color::Red => class_list.push("bg2"),
+            Color::None => {}
+        }
+
+        match context.text.shadow_color {
+            Color::Black => class_list.push("sh1"),
+            Color::Red => class_list.push("sh2"),
+            Color::None => {}
         }
 
         if context.text.underline == TextUnderline::On {
@@ -148,20 +226,156 @@ impl CommandRenderer for HtmlRenderer {
             class_list.push("ud");
         }
 
-        if context.text.upside_down == true {
-            class_list.push("upd");
+        //Right-side character spacing (ESC SP)
+        let spacing = context.char_spacing_pixels() as f32 * self.pixel_scale_ratio;
+        let width_mult = context.text.width_mult.max(1) as usize;
+        let height_mult = context.text.height_mult.max(1) as usize;
+        let line_height = self.current_line_height as f32 * self.pixel_scale_ratio;
+        let rotated = context.is_rotated();
+        let span_attrs = |line: &str| {
+            //Rotated characters run down the paper as far as they are wide, so
+            //the line spacing is multiplied by the width and not by the height
+            if rotated {
+                return format!(
+                    "class='{} sz' style='font-size: {}em; line-height: {}px; transform: scaleY({});'",
+                    class_list.join(" "),
+                    height_mult,
+                    line_height * width_mult as f32,
+                    width_mult as f32 / height_mult as f32
+                );
+            }
+
+            if width_mult == 1 && height_mult == 1 {
+                return match spacing > 0.0 {
+                    true => format!(
+                        "class='{}' style='letter-spacing: {}px'",
+                        class_list.join(" "),
+                        spacing
+                    ),
+                    false => format!("class='{}'", class_list.join(" ")),
+                };
+            }
+
+            //Enlarged characters are set at their height and squeezed or stretched
+            //to their width, the box keeps the printed width so the line lays out
+            //like on paper and its bottom lines up with the other characters
+            let chars = line.chars().count();
+            let scale_x = width_mult as f32 / height_mult as f32;
+            format!(
+                "class='{} sz' style='font-size: {}em; line-height: {}px; width: calc({}ch + {}px); letter-spacing: {}px; transform: scaleX({});'",
+                class_list.join(" "),
+                height_mult,
+                line_height * height_mult as f32,
+                chars as f32 * scale_x,
+                chars as f32 * spacing,
+                spacing / scale_x,
+                scale_x
+            )
+        };
+        //Empty segments are left out so a trailing newline doesn't open an empty line,
+        //upside-down lines are kept in a block of their own that is turned around
+        let segments = text.split('\n').collect::<Vec<&str>>();
+        let mut br_text = String::new();
+        for (i, line) in segments.iter().enumerate() {
+            if !line.is_empty() {
+                if context.is_upside_down() && !self.upside_down_line {
+                    br_text.push_str("<div class='upd'>");
+                    self.upside_down_line = true;
+                }
+                br_text.push_str(&format!(
+                    "<span {}>{}</span>",
+                    span_attrs(line),
+                    rotate_chars(context, line, self.pixel_scale_ratio)
+                ));
+            }
+            if i + 1 < segments.len() {
+                match self.upside_down_line {
+                    true => br_text.push_str("</div>"),
+                    false => br_text.push_str("<br/>"),
+                }
+                self.upside_down_line = false;
+            }
         }
 
-        let css_class = class_list.join(" ");
-        let br_text = text.replace("\n", &*format!("</span><br/><span class='{}'>", css_class));
+        //Keep the context y in step with the lines so feeds can be limited
+        context.graphics.y += text.matches('\n').count() * context.line_height_pixels() as usize;
+
+        let line_text = match text.rfind(|c| c == '\n' || c == '\r') {
+            Some(pos) => {
+                self.line_x_pixels = 0.0;
+                self.line_x_chars = 0;
+                &text[pos + 1..]
+            }
+            None => &text,
+        };
+        self.line_x_chars += line_text.chars().count() * width_mult;
+        self.line_x_pixels += line_text.chars().count() as f32 * spacing;
+
+        self.content.push(br_text)
+    }
+
+    fn draw_device_command(&mut self, context: &mut Context, command: &DeviceCommand) {
+        match command {
+            DeviceCommand::BeginPage | DeviceCommand::EndPage | DeviceCommand::Initialize => {}
+            _ => self.maybe_start_region(context),
+        }
 
-        self.content
-            .push(format!("<span class='{}'>{}</span>", css_class, br_text))
+        match command {
+            DeviceCommand::MoveX(n) => {
+                let pos =
+                    (*n as u32 * context.motion_unit_x_pixels()) as f32 * self.pixel_scale_ratio;
+                self.content.push(format!(
+                    "<span class='mx' style='margin-left: calc({}px - {}ch)'></span>",
+                    pos - self.line_x_pixels,
+                    self.line_x_chars
+                ));
+                self.line_x_pixels = pos;
+                self.line_x_chars = 0;
+            }
+            DeviceCommand::MoveXRelative(n) => {
+                let offset = (*n as i32 * context.motion_unit_x_pixels() as i32) as f32
+                    * self.pixel_scale_ratio;
+                self.content.push(format!(
+                    "<span class='mx' style='margin-left: {}px'></span>",
+                    offset
+                ));
+                self.line_x_pixels += offset;
+            }
+            DeviceCommand::Feed(_) | DeviceCommand::FeedLine(_) => {
+                if let Some(offset) = feed_pixels(context, command) {
+                    self.push_feed(offset as f32);
+                }
+            }
+            DeviceCommand::MoveY(n) => {
+                let y = *n as f32 * context.motion_unit_y_pixels() as f32;
+                self.push_feed(y - context.graphics.y as f32);
+            }
+            DeviceCommand::MoveYRelative(n) => {
+                let offset = *n as i64 * context.motion_unit_y_pixels() as i64;
+                self.push_feed(offset.max(0 - context.graphics.y as i64) as f32);
+            }
+            DeviceCommand::BeginPage => self.begin_page(context),
+            DeviceCommand::PrintPage => self.print_page(context),
+            DeviceCommand::EndPage | DeviceCommand::Initialize => self.end_page(context),
+            //Drawer kicks have no printed output, so they're marked where they happened
+            DeviceCommand::Pulse { pin, on_ms, off_ms } => {
+                self.content.push(format!(
+                    "<div class='ev'>Drawer kick pin {} ({}ms on, {}ms off)</div>",
+                    pin, on_ms, off_ms
+                ));
+            }
+            //Clears the current region
+            DeviceCommand::Cancel if self.page_region.is_some() => {
+                self.content.truncate(self.page_region_start);
+                self.start_container(context);
+            }
+            _ => {}
+        }
     }
 
-    fn draw_device_command(&mut self, _context: &mut Context, _command: &DeviceCommand) {}
+    fn end_render(&mut self, context: &mut Context) {
+        self.end_page(context);
 
-    fn end_render(&mut self, _context: &mut Context) {
         //Close the last container
         self.end_container();
 
@@ -171,10 +385,13 @@ impl CommandRenderer for HtmlRenderer {
             .replace(
                 "{{receipt-style}}",
                 &*format!(
-                    "max-width: {}px; padding-left: {}px; padding-right: {}px;",
+                    "max-width: {}px; padding-left: {}px; padding-right: {}px; --ink2: rgb({}, {}, {});",
                     self.receipt_width_pixels,
                     self.receipt_margin_left_pixels,
-                    self.receipt_margin_right_pixels
+                    self.receipt_margin_right_pixels,
+                    self.second_ink[0],
+                    self.second_ink[1],
+                    self.second_ink[2]
                 ),
             )
             .replace("{{font-size}}", &self.font_size_pixels.to_string());
@@ -182,12 +399,19 @@ impl CommandRenderer for HtmlRenderer {
         std::fs::write(PathBuf::from(format!("{}{}", self.out_path, ".html")), out)
             .expect("Invalid out path");
     }
+
+    fn held_commands(&mut self) -> &mut Vec<Command> {
+        &mut self.held_commands
+    }
 }
 
 impl HtmlRenderer {
     fn maybe_start_container(&mut self, context: &mut Context) {
-        //No need if justification hasn't changed
-        if self.current_justify == context.text.justify {
+        //No need if justification, print area and line spacing haven't changed
+        if self.current_justify == context.text.justify
+            && self.current_print_area == Self::print_area(context)
+            && self.current_line_height == context.line_height_pixels()
+        {
             return;
         }
 
@@ -197,27 +421,194 @@ impl HtmlRenderer {
 
     fn start_container(&mut self, context: &mut Context) {
         self.current_justify = context.text.justify.clone();
+        self.current_print_area = Self::print_area(context);
+        self.current_line_height = context.line_height_pixels();
         let css_class = match self.current_justify {
             TextJustify::Left => String::from("al"),
             TextJustify::Center => String::from("ac"),
             TextJustify::Right => String::from("ar"),
         };
 
-        self.content
-            .push(format!("<div class='cnt {}'>", css_class));
+        //Left margin (GS L) and print area width (GS W)
+        let (left, width) = self.current_print_area;
+        self.content.push(format!(
+            "<div class='cnt {}' style='margin-left: {}px; width: {}px; line-height: {}px;'>",
+            css_class,
+            left as f32 * self.pixel_scale_ratio,
+            width as f32 * self.pixel_scale_ratio,
+            self.current_line_height as f32 * self.pixel_scale_ratio
+        ));
+        self.line_x_pixels = 0.0;
+        self.line_x_chars = 0;
+    }
+
+    fn begin_page(&mut self, context: &mut Context) {
+        if self.paper.is_some() {
+            return;
+        }
+        self.end_container();
+        self.paper = Some(std::mem::take(&mut self.content));
+        self.paper_y = context.graphics.y;
+        self.page_region = None;
+        self.page_height = 0;
+
+        //Page content is positioned from the start of the print area
+        context.graphics.x = 0;
+        context.graphics.y = 0;
+        context.graphics.y_max = 0;
+    }
+
+    //Starts a new region when the print area or direction changes
+    fn maybe_start_region(&mut self, context: &mut Context) {
+        if self.paper.is_none() {
+            return;
+        }
+        let region = (context.page_area(), context.graphics.page_direction.clone());
+        if self.page_region.as_ref() == Some(&region) {
+            self.page_region_y = self.page_region_y.max(context.graphics.y);
+            return;
+        }
+        self.end_region(context);
+
+        //Regions are laid out along the print direction and then rotated into place
+        let (area, direction) = &region;
+        let (width, height) = context.page_area_logical_size();
+        let (left, top, rotation) = match direction {
+            PrintDirection::LeftToRight => (area.x, area.y, 0),
+            PrintDirection::BottomToTop => (area.x, area.y + area.height, -90),
+            PrintDirection::RightToLeft => (area.x + area.width, area.y + area.height, 180),
+            PrintDirection::TopToBottom => (area.x + area.width, area.y, 90),
+        };
+        self.content.push(format!(
+            "<div class='rg' style='left: {}px; top: {}px; width: {}px; height: {}px; transform: rotate({}deg);'>",
+            left as f32 * self.pixel_scale_ratio,
+            top as f32 * self.pixel_scale_ratio,
+            width as f32 * self.pixel_scale_ratio,
+            height as f32 * self.pixel_scale_ratio,
+            rotation
+        ));
+
+        self.page_region = Some(region);
+        self.page_region_start = self.content.len();
+        self.page_region_y = context.graphics.y;
+        self.start_container(context);
+    }
+
+    fn end_region(&mut self, context: &Context) {
+        if self.page_region.is_some() {
+            self.page_height = self.page_height.max(self.region_bottom(context));
+            self.page_region = None;
+            self.end_container();
+            self.content.push(String::from("</div>"));
+        }
+    }
+
+    //Bottom of the current region on the page, only the used part of
+    //an unrotated region is printed
+    fn region_bottom(&self, context: &Context) -> u32 {
+        match &self.page_region {
+            Some((area, PrintDirection::LeftToRight)) => {
+                area.y + (context.graphics.y as u32 + context.line_height_pixels()).min(area.height)
+            }
+            Some((area, _)) => area.y + area.height,
+            None => 0,
+        }
+    }
+
+    fn print_page(&mut self, context: &mut Context) {
+        let mut page = self.content.clone();
+        let mut height = self.page_height;
+        if self.page_region.is_some() {
+            //Closes the open container and region
+            page.push(String::from("</div></div>"));
+            height = height.max(self.region_bottom(context));
+        }
+
+        if let Some(paper) = &mut self.paper {
+            paper.push(format!(
+                "<div class='pg' style='height: {}px'>",
+                height as f32 * self.pixel_scale_ratio
+            ));
+            paper.extend(page);
+            paper.push(String::from("</div>"));
+            self.paper_y += height as usize;
+            context.feed_paper(height);
+        }
+    }
+
+    //Unprinted page data is lost when leaving page mode
+    fn end_page(&mut self, context: &mut Context) {
+        if let Some(paper) = self.paper.take() {
+            self.content = paper;
+            self.page_region = None;
+            self.page_height = 0;
+            context.graphics.x = 0;
+            context.graphics.y = self.paper_y;
+            context.graphics.y_max = self.paper_y;
+            self.start_container(context);
+        }
+    }
+
+    //Paper feed as empty space of the given height in pixels, reverse
+    //feeds pull the following content up so it overprints what is above
+    fn push_feed(&mut self, height: f32) {
+        self.end_upside_down_line();
+        if height >= 0.0 {
+            self.content.push(format!(
+                "<div class='fd' style='height: {}px'></div>",
+                height * self.pixel_scale_ratio
+            ));
+        } else {
+            self.content.push(format!(
+                "<div class='fd' style='margin-top: {}px'></div>",
+                height * self.pixel_scale_ratio
+            ));
+        }
+        self.line_x_pixels = 0.0;
+        self.line_x_chars = 0;
+    }
+
+    //Upside-down lines are turned around the center of the paper,
+    //so the print area ends up on the other side
+    fn print_area(context: &Context) -> (u32, u32) {
+        let left = context.print_area_left_pixels();
+        let width = context.available_width_pixels();
+        match context.is_upside_down() {
+            true => (
+                context
+                    .printable_width_pixels()
+                    .saturating_sub(left + width),
+                width,
+            ),
+            false => (left, width),
+        }
     }
 
     fn end_container(&mut self) {
+        self.end_upside_down_line();
         self.content.push(String::from("</div>"));
     }
 
-    fn encode_html_image(&self, bytes: Vec<u8>, width: u32, height: u32) -> String {
+    fn end_upside_down_line(&mut self) {
+        if self.upside_down_line {
+            self.content.push(String::from("</div>"));
+            self.upside_down_line = false;
+        }
+    }
+
+    fn encode_html_image(
+        &self,
+        bytes: Vec<u8>,
+        width: u32,
+        height: u32,
+        color_type: ColorType,
+    ) -> String {
         // Create a buffer to hold the PNG image data
         let mut png_data: Vec<u8> = Vec::new();
 
         // Create a PNG encoder with the specified width, height, and color type
         let mut encoder = Encoder::new(&mut png_data, width, height);
-        encoder.set_color(ColorType::Grayscale);
+        encoder.set_color(color_type);
         encoder.set_depth(png::BitDepth::Eight);
 
         // Write the PNG header and the image data
@@ -239,3 +630,29 @@ impl HtmlRenderer {
         )
     }
 }
+
+//Images and codes printed upside-down are turned around with their line
+fn upside_down_block(context: &Context, html: String) -> String {
+    match context.is_upside_down() {
+        true => format!("<div class='upd'>{}</div>", html),
+        false => html,
+    }
+}
+
+//Rotated characters (ESC V) are turned one by one in their own cell,
+//which is as wide as a character is high plus the rotation spacing
+fn rotate_chars(context: &Context, line: &str, pixel_scale_ratio: f32) -> String {
+    if !context.is_rotated() {
+        return line.to_string();
+    }
+    let spacing = context.rotation_spacing_pixels() as f32 * pixel_scale_ratio;
+    line.chars()
+        .map(|c| match spacing > 0.0 {
+            true => format!(
+                "<span class='rt' style='margin-right: {}px'>{}</span>",
+                spacing, c
+            ),
+            false => format!("<span class='rt'>{}</span>", c),
+        })
+        .collect::<String>()
+}
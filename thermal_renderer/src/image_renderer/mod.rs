@@ -1,8 +1,10 @@
-use crate::image_renderer::thermal_image::{FontFamily, TextLayout, TextSpan, ThermalImage};
+use crate::image_renderer::thermal_image::{
+    FontFamily, Ink, TextLayout, TextMove, TextSpan, ThermalImage,
+};
 use crate::renderer::CommandRenderer;
 use std::rc::Rc;
-use thermal_parser::command::DeviceCommand;
-use thermal_parser::context::Context;
+use thermal_parser::command::{Command, DeviceCommand};
+use thermal_parser::context::{Color, Context, PageArea, PrintDirection};
 
 pub mod thermal_image;
 
@@ -10,6 +12,16 @@ pub struct ImageRenderer {
     pub image: ThermalImage,
     pub text_layout: Option<TextLayout>,
     pub out_path: String,
+    pub second_ink: [u8; 3], //rgb of the second color (ESC r, GS ( N) in the output
+    //While in page mode the paper is set aside and each print area of the page
+    //is composed in image along its print direction before it's placed in the page
+    pub paper: Option<ThermalImage>,
+    pub paper_y: usize,
+    pub page: Option<ThermalImage>,
+    pub page_region: Option<(PageArea, PrintDirection)>,
+    //Area covered by the barcode or 2D code being drawn as x, y, right, bottom
+    pub graphics_area: Option<(usize, usize, usize, usize)>,
+    pub held_commands: Vec<Command>,
     out_count: u32,
 }
 
@@ -20,33 +32,25 @@ impl ImageRenderer {
             fontdue::FontSettings::default(),
         )
         .unwrap();
-        let bold = fontdue::Font::from_bytes(
-            include_bytes!("../../resources/fonts/JetBrainsMonoNL-Bold.ttf") as &[u8],
-            fontdue::FontSettings::default(),
-        )
-        .unwrap();
         let italic = fontdue::Font::from_bytes(
             include_bytes!("../../resources/fonts/JetBrainsMonoNL-MediumItalic.ttf") as &[u8],
             fontdue::FontSettings::default(),
         )
         .unwrap();
-        let bold_italic = fontdue::Font::from_bytes(
-            include_bytes!("../../resources/fonts/JetBrainsMonoNL-BoldItalic.ttf") as &[u8],
-            fontdue::FontSettings::default(),
-        )
-        .unwrap();
 
-        let fonts = Rc::from(FontFamily {
-            regular,
-            bold,
-            italic,
-            bold_italic,
-        });
+        let fonts = Rc::from(FontFamily { regular, italic });
 
         Self {
             image: ThermalImage::new(fonts.clone(), 0),
             text_layout: None,
             out_path,
+            second_ink: [200, 16, 32],
+            paper: None,
+            paper_y: 0,
+            page: None,
+            page_region: None,
+            graphics_area: None,
+            held_commands: vec![],
             out_count: 0,
         }
     }
@@ -55,65 +59,145 @@ impl ImageRenderer {
 impl CommandRenderer for ImageRenderer {
     fn begin_render(&mut self, context: &mut Context) {
         self.image
-            .set_width(context.available_width_pixels() as usize);
+            .set_width(context.printable_width_pixels() as usize);
     }
 
     fn begin_graphics(&mut self, context: &mut Context) {
+        self.maybe_start_region(context);
         self.maybe_render_text(context);
+        self.graphics_area = None;
     }
 
     fn draw_rect(&mut self, context: &mut Context, w: usize, h: usize) {
-        self.image
-            .draw_rect(context.graphics.x, context.graphics.y, w, h);
+        let (x, y) = (context.graphics.x, context.graphics.y);
+        self.image.draw_rect(x, y, w, h);
+        self.graphics_area = match self.graphics_area {
+            Some((x0, y0, x1, y1)) => Some((x0.min(x), y0.min(y), x1.max(x + w), y1.max(y + h))),
+            None => Some((x, y, x + w, y + h)),
+        };
+    }
+
+    fn end_graphics(&mut self, context: &mut Context) {
+        if let Some((x0, y0, x1, y1)) = self.graphics_area.take() {
+            if context.is_upside_down() {
+                self.image.flip_line_pixels(x0, y0, x1 - x0, y1 - y0);
+            }
+        }
     }
-    fn end_graphics(&mut self, _context: &mut Context) {}
 
     fn draw_image(&mut self, context: &mut Context, bytes: Vec<u8>, width: usize, height: usize) {
+        self.maybe_start_region(context);
         self.maybe_render_text(context);
-        self.image.put_pixels(
+        self.image.put_tones(
             context.graphics.x,
             context.graphics.y,
             width,
             height,
-            bytes,
-            false,
-            true,
+            &bytes,
         );
-        if context.text.upside_down {
+        if context.is_upside_down() {
             self.image
-                .flip_pixels(context.graphics.x, context.graphics.y, width, height)
+                .flip_line_pixels(context.graphics.x, context.graphics.y, width, height)
         }
     }
 
-    fn draw_text(&mut self, context: &mut Context, text: String) {
-        //Here we are avoiding using text layout for single newlines
-        //by advancing the newline manually when the text layout is empty
-        if self.text_layout.is_none() && text.eq("\n") {
-            context.graphics.y += context.text.line_spacing as usize;
-            return;
+    fn draw_color_image(
+        &mut self,
+        context: &mut Context,
+        bytes: Vec<u8>,
+        colors: Vec<Color>,
+        width: usize,
+        height: usize,
+    ) {
+        self.maybe_start_region(context);
+        self.maybe_render_text(context);
+
+        //Each ink is put down separately
+        for color in [Color::Red, Color::Black] {
+            let layer = bytes
+                .iter()
+                .zip(&colors)
+                .map(|(pixel, c)| if *c == color { *pixel } else { 255 })
+                .collect::<Vec<u8>>();
+            self.image.ink = Ink::from_color(&color).unwrap_or(Ink::First);
+            self.image.put_tones(
+                context.graphics.x,
+                context.graphics.y,
+                width,
+                height,
+                &layer,
+            );
         }
+        self.image.ink = Ink::First;
 
-        let span = TextSpan::new(self.image.font.clone(), text.to_string(), context);
+        if context.is_upside_down() {
+            self.image
+                .flip_line_pixels(context.graphics.x, context.graphics.y, width, height)
+        }
+    }
+
+    fn draw_text(&mut self, context: &mut Context, text: String) {
+        self.maybe_start_region(context);
 
+        //Here we are avoiding using text layout for leading newlines
+        //by advancing the newlines manually when the text layout is empty
+        let mut text = text.as_str();
         if self.text_layout.is_none() {
-            self.text_layout = Some(TextLayout {
-                spans: vec![span],
-                line_height: context.line_height_pixels() as usize,
-                tab_len: context.text.tab_len as usize,
-            });
-        } else {
-            if let Some(layout) = &mut self.text_layout {
-                layout.spans.push(span);
+            while let Some(rest) = text.strip_prefix('\n') {
+                context.graphics.y += context.line_height_pixels() as usize;
+                text = rest;
+            }
+            if text.is_empty() {
+                return;
             }
         }
+
+        let span = TextSpan::new(self.image.font.clone(), text.to_string(), context);
+        self.push_span(context, span);
     }
 
-    fn draw_device_command(&mut self, context: &mut Context, _command: &DeviceCommand) {
-        self.maybe_render_text(context);
+    fn draw_device_command(&mut self, context: &mut Context, command: &DeviceCommand) {
+        //Horizontal moves stay within the current line so the text is kept in the layout
+        match command {
+            DeviceCommand::MoveX(n) => {
+                let pos = *n as usize * context.motion_unit_x_pixels() as usize;
+                let span =
+                    TextSpan::new_move(self.image.font.clone(), TextMove::Absolute(pos), context);
+                self.push_span(context, span);
+            }
+            DeviceCommand::MoveXRelative(n) => {
+                let offset = *n as i32 * context.motion_unit_x_pixels() as i32;
+                let span = TextSpan::new_move(
+                    self.image.font.clone(),
+                    TextMove::Relative(offset),
+                    context,
+                );
+                self.push_span(context, span);
+            }
+            DeviceCommand::BeginPage => {
+                self.maybe_render_text(context);
+                self.begin_page(context);
+            }
+            DeviceCommand::EndPage | DeviceCommand::Initialize => {
+                self.maybe_render_text(context);
+                self.end_page(context);
+            }
+            _ => {
+                self.maybe_start_region(context);
+                self.maybe_render_text(context);
+
+                match command {
+                    DeviceCommand::PrintPage => self.print_page(context),
+                    DeviceCommand::Cancel => self.cancel_region(),
+                    _ => {}
+                }
+            }
+        }
     }
 
     fn end_render(&mut self, context: &mut Context) {
         self.maybe_render_text(context);
+        self.end_page(context);
 
         //Simulate post cut feeding
         self.image
@@ -129,11 +213,16 @@ impl CommandRenderer for ImageRenderer {
 
         let out_path = self.unique_out_path();
 
-        self.image.save_png(out_path);
+        self.image.save_png(out_path, self.second_ink);
 
         self.image.reset();
         context.graphics.x = 0;
         context.graphics.y = 0;
+        context.graphics.y_max = 0;
+    }
+
+    fn held_commands(&mut self) -> &mut Vec<Command> {
+        &mut self.held_commands
     }
 }
 
@@ -142,15 +231,143 @@ impl ImageRenderer {
         self.out_count = self.out_count.wrapping_add(1);
         format!("{}.png", self.out_path.to_string())
     }
+    fn push_span(&mut self, context: &mut Context, span: TextSpan) {
+        self.maybe_start_region(context);
+
+        if self.text_layout.is_none() {
+            self.text_layout = Some(TextLayout {
+                spans: vec![span],
+                origin: (context.graphics.x, context.graphics.y),
+                line_height: context.line_height_pixels() as usize,
+                tab_len: context.text.tab_len as usize,
+            });
+        } else {
+            if let Some(layout) = &mut self.text_layout {
+                layout.spans.push(span);
+            }
+        }
+    }
+    fn begin_page(&mut self, context: &mut Context) {
+        if self.paper.is_some() {
+            return;
+        }
+        let page = ThermalImage::new(self.image.font.clone(), self.image.width);
+        self.paper = Some(std::mem::replace(&mut self.image, page));
+        self.page = Some(ThermalImage::new(
+            self.image.font.clone(),
+            context.printable_width_pixels() as usize,
+        ));
+        self.page_region = None;
+        self.paper_y = context.graphics.y;
+
+        //Page content is positioned from the start of the print area
+        context.graphics.x = 0;
+        context.graphics.y = 0;
+        context.graphics.y_max = 0;
+    }
+
+    //Starts composing a new region when the print area or direction changes
+    fn maybe_start_region(&mut self, context: &mut Context) {
+        if self.page.is_none() {
+            return;
+        }
+        let region = (context.page_area(), context.graphics.page_direction.clone());
+        if self.page_region.as_ref() == Some(&region) {
+            return;
+        }
+
+        self.maybe_render_text(context);
+        self.place_region(context);
+
+        let (width, _) = context.page_area_logical_size();
+        self.image = ThermalImage::new(self.image.font.clone(), width as usize);
+        self.page_region = Some(region);
+    }
+
+    //Rotates the current region into place on the page, the region is kept
+    //so it can still be added to
+    fn place_region(&mut self, context: &Context) {
+        if let (Some(page), Some((area, direction))) = (&mut self.page, &self.page_region) {
+            let mut region = ThermalImage::new(self.image.font.clone(), self.image.width);
+            region.put_image(0, 0, &self.image);
+
+            let logical_height = match direction.is_vertical() {
+                true => area.width as usize,
+                false => area.height as usize,
+            };
+
+            //Only the used part of an unrotated region is printed
+            match direction {
+                PrintDirection::LeftToRight => {
+                    region.set_height(region.height().max(context.graphics.y).min(logical_height))
+                }
+                _ => region.set_height(logical_height),
+            }
+
+            region.rotate(match direction {
+                PrintDirection::LeftToRight => 0,
+                PrintDirection::TopToBottom => 1,
+                PrintDirection::RightToLeft => 2,
+                PrintDirection::BottomToTop => 3,
+            });
+
+            page.put_image(area.x as usize, area.y as usize, &region);
+        }
+    }
+
+    //Clears the print area of the current region
+    fn cancel_region(&mut self) {
+        if let (Some(page), Some((area, _))) = (&mut self.page, &self.page_region) {
+            self.image.reset();
+            page.clear_rect(
+                area.x as usize,
+                area.y as usize,
+                area.width as usize,
+                area.height as usize,
+            );
+        }
+    }
+
+    fn print_page(&mut self, context: &mut Context) {
+        self.place_region(context);
+        if let (Some(paper), Some(page)) = (&mut self.paper, &self.page) {
+            paper.put_image(0, self.paper_y, page);
+            self.paper_y += page.height();
+            context.feed_paper(page.height() as u32);
+        }
+    }
+
+    //Unprinted page data is lost when leaving page mode
+    fn end_page(&mut self, context: &mut Context) {
+        if let Some(paper) = self.paper.take() {
+            self.image = paper;
+            self.page = None;
+            self.page_region = None;
+            context.graphics.x = 0;
+            context.graphics.y = self.paper_y;
+            context.graphics.y_max = self.paper_y;
+        }
+    }
+
     pub fn maybe_render_text(&mut self, context: &mut Context) {
         if let Some(layout) = &mut self.text_layout {
-            let (_, y) = self.image.draw_text(
-                context.graphics.x as usize,
-                context.graphics.y as usize,
-                self.image.width,
-                layout,
-            );
-            context.graphics.y = y;
+            //Text in page mode sits on the print position
+            let ascent = layout
+                .spans
+                .iter()
+                .find(|span| span.move_x.is_none())
+                .map(|span| span.cell_height() * span.stretch_height as usize)
+                .unwrap_or(0);
+            //A first line too close to the top is pushed down to fit, the lines
+            //after it keep their baseline below the top of the line
+            let (x, y) = layout.origin;
+            let (top, baseline) = match context.is_page_mode {
+                true => (y.saturating_sub(ascent), ascent),
+                false => (y, 0),
+            };
+
+            let (_, new_y) = self.image.draw_text(x, top, self.image.width, layout);
+            context.graphics.y = new_y + baseline;
             self.text_layout = None;
         }
     }
@@ -12,23 +12,46 @@ use fontdue::Font;
 use png::BitDepth;
 use textwrap::WordSeparator;
 
-use thermal_parser::context::{Context, TextJustify, TextStrikethrough, TextUnderline};
+use thermal_parser::context::{Color, Context, TextJustify, TextStrikethrough, TextUnderline};
 
 const THRESHOLD: u8 = 120;
 const SCALE_THRESHOLD: u8 = 140;
 
+//Emphasis is printed by doubling dots rather than with a bold face
 pub struct FontFamily {
     pub regular: fontdue::Font,
-    pub bold: fontdue::Font,
     pub italic: fontdue::Font,
-    pub bold_italic: fontdue::Font,
+}
+
+//Ink a dot is printed with, two color printers have a second ink (usually red)
+#[derive(Clone, Copy, PartialEq)]
+pub enum Ink {
+    First,
+    Second,
+}
+
+impl Ink {
+    pub fn from_color(color: &Color) -> Option<Ink> {
+        match color {
+            Color::None => None,
+            Color::Black => Some(Ink::First),
+            Color::Red => Some(Ink::Second),
+        }
+    }
+}
+
+//Moves the text cursor within the current line, in pixels
+#[derive(Clone, Copy)]
+pub enum TextMove {
+    Absolute(usize),
+    Relative(i32),
 }
 
 pub struct TextSpan {
     pub font: Rc<FontFamily>,
     pub size: u32,
     pub text: String,
-    pub bold: bool,
+    pub bold: bool, //emphasis (ESC E) and double strike (ESC G)
     pub italic: bool,
     pub underline: usize,
     pub strikethrough: usize,
@@ -37,6 +60,17 @@ pub struct TextSpan {
     pub inverted: bool,
     pub upside_down: bool,
     pub justify: TextJustify,
+    pub move_x: Option<TextMove>,
+    pub margin_left: usize,
+    pub area_width: usize,
+    pub char_spacing: usize,
+    pub line_height: usize,
+    pub rotated: bool,
+    pub rotation_spacing: usize,
+    pub smoothing: bool,
+    pub color: Color,
+    pub background_color: Color,
+    pub shadow_color: Color,
 }
 
 impl TextSpan {
@@ -59,31 +93,77 @@ impl TextSpan {
             font,
             size: context.font_size_pixels(),
             text,
-            bold: style.bold,
+            bold: style.bold || style.double_strike,
             italic: style.italic,
             underline,
             strikethrough,
             stretch_width: style.width_mult as f32,
             stretch_height: style.height_mult as f32,
             inverted: style.invert,
-            upside_down: style.upside_down,
+            upside_down: context.is_upside_down(),
             justify: context.text.justify.clone(),
+            move_x: None,
+            margin_left: context.print_area_left_pixels() as usize,
+            area_width: context.available_width_pixels() as usize,
+            char_spacing: context.char_spacing_pixels() as usize,
+            line_height: context.line_height_pixels() as usize,
+            rotated: context.is_rotated(),
+            rotation_spacing: context.rotation_spacing_pixels() as usize,
+            smoothing: style.smoothing,
+            color: style.color.clone(),
+            background_color: style.background_color.clone(),
+            shadow_color: style.shadow_color.clone(),
         }
     }
 
+    pub fn new_move(font: Rc<FontFamily>, move_x: TextMove, context: &Context) -> Self {
+        let mut span = Self::new(font, String::new(), context);
+        span.move_x = Some(move_x);
+        span
+    }
+
     pub fn char_width(&self) -> usize {
+        //Rotated characters take up their height along the line
+        if self.rotated {
+            return self.cell_height() * self.stretch_height as usize
+                + self.rotation_spacing
+                + self.char_spacing;
+        }
+        self.glyph_width() + self.char_spacing
+    }
+
+    //Width of an unrotated character without spacing
+    pub fn glyph_width(&self) -> usize {
         let metrics = self.font.regular.metrics(' ', self.size as f32);
         metrics.advance_width.floor() as usize * self.stretch_width as usize
     }
 
-    // TODO: get font should according to cjk of context.
-    pub fn get_font(&self) -> &Font {
-        if self.bold && self.italic {
-            return &self.font.bold_italic;
+    //Height a character takes up on the paper
+    pub fn paper_height(&self) -> usize {
+        if self.rotated {
+            return self.glyph_width();
         }
-        if self.bold {
-            return &self.font.bold;
+        self.cell_height() * self.stretch_height as usize
+    }
+
+    //Line spacing multiplier, the enlargement that ends up along the paper
+    pub fn line_mult(&self) -> usize {
+        if self.rotated {
+            return self.stretch_width as usize;
         }
+        self.stretch_height as usize
+    }
+
+    //Height of an unscaled character cell
+    pub fn cell_height(&self) -> usize {
+        match self.font.regular.horizontal_line_metrics(self.size as f32) {
+            Some(metrics) => (metrics.ascent - metrics.descent).ceil() as usize,
+            None => self.size as usize,
+        }
+    }
+
+    // TODO: get font should according to cjk of context.
+    pub fn get_font(&self) -> &Font {
         if self.italic {
             return &self.font.italic;
         }
@@ -93,6 +173,7 @@ impl TextSpan {
 
 pub struct TextLayout {
     pub spans: Vec<TextSpan>,
+    pub origin: (usize, usize), //print position when the layout was started
     pub line_height: usize,
     pub tab_len: usize,
 }
@@ -102,16 +183,20 @@ pub struct TextLayout {
 /// to accommodate sets of pixels being pushed at arbitrary x and y values
 pub struct ThermalImage {
     bytes: Vec<u8>,
+    inks: Vec<Ink>, //ink of each pixel, kept in step with bytes
     pub width: usize,
     pub font: Rc<FontFamily>,
+    pub ink: Ink, //ink used for drawing
 }
 
 impl ThermalImage {
     pub fn new(font: Rc<FontFamily>, width: usize) -> Self {
         Self {
             bytes: Vec::<u8>::new(),
+            inks: Vec::<Ink>::new(),
             font,
             width,
+            ink: Ink::First,
         }
     }
 
@@ -119,11 +204,95 @@ impl ThermalImage {
     pub fn set_width(&mut self, width: usize) {
         self.width = width;
         self.bytes = Vec::<u8>::new();
+        self.inks = Vec::<Ink>::new();
     }
 
     pub fn reset(&mut self) {
         self.bytes.clear();
         self.bytes.shrink_to(0);
+        self.inks.clear();
+        self.inks.shrink_to(0);
+    }
+
+    pub fn height(&self) -> usize {
+        if self.width == 0 {
+            return 0;
+        }
+        self.bytes.len() / self.width
+    }
+
+    //Multiplies another image onto this one, keeping the inks of its pixels
+    pub fn put_image(&mut self, x: usize, y: usize, image: &ThermalImage) {
+        if image.height() == 0 || x >= self.width {
+            return;
+        }
+        self.ensure_height(y + image.height());
+
+        for cur_y in 0..image.height() {
+            for cur_x in 0..image.width.min(self.width - x) {
+                let from = cur_y * image.width + cur_x;
+                let idx = (y + cur_y) * self.width + x + cur_x;
+                if image.bytes[from] < self.bytes[idx] {
+                    self.bytes[idx] = image.bytes[from];
+                    self.inks[idx] = image.inks[from];
+                }
+            }
+        }
+    }
+
+    //Multiplies grayscale pixels onto the image keeping their tones,
+    //pixels past the right edge are clipped
+    pub fn put_tones(&mut self, x: usize, y: usize, width: usize, height: usize, pixels: &[u8]) {
+        if x >= self.width || pixels.len() < width * height {
+            return;
+        }
+        self.ensure_height(y + height);
+
+        for cur_y in 0..height {
+            for cur_x in 0..width.min(self.width - x) {
+                let pixel = pixels[cur_y * width + cur_x];
+                let idx = (y + cur_y) * self.width + x + cur_x;
+                if pixel < self.bytes[idx] {
+                    self.bytes[idx] = pixel;
+                    self.inks[idx] = self.ink;
+                }
+            }
+        }
+    }
+
+    //Cuts off or extends the image to the given height
+    pub fn set_height(&mut self, height: usize) {
+        self.bytes.resize(self.width * height, 255u8);
+        self.inks.resize(self.width * height, Ink::First);
+    }
+
+    //Rotates the image clockwise by the given number of quarter turns
+    pub fn rotate(&mut self, quarter_turns: usize) {
+        for _ in 0..quarter_turns % 4 {
+            let width = self.width;
+            let height = self.height();
+            let mut rotated = Vec::<u8>::with_capacity(self.bytes.len());
+            let mut rotated_inks = Vec::<Ink>::with_capacity(self.inks.len());
+
+            for y in 0..width {
+                for x in 0..height {
+                    rotated.push(self.bytes[(height - 1 - x) * width + y]);
+                    rotated_inks.push(self.inks[(height - 1 - x) * width + y]);
+                }
+            }
+
+            self.bytes = rotated;
+            self.inks = rotated_inks;
+            self.width = height;
+        }
+    }
+
+    pub fn clear_rect(&mut self, x: usize, y: usize, w: usize, h: usize) {
+        let h = h.min(self.height().saturating_sub(y));
+        if h == 0 {
+            return;
+        }
+        self.put_pixels(x, y, w, h, vec![255u8; w * h], false, false);
     }
 
     pub fn draw_rect(&mut self, x: usize, y: usize, w: usize, h: usize) {
@@ -140,8 +309,28 @@ impl ThermalImage {
         let mut temp_x = 0;
         let newline = Vec::<(&TextSpan, String, usize)>::new();
         let mut lines = vec![newline.clone()];
+        //Line spacing in effect when each line was ended
+        let mut line_heights = Vec::<usize>::new();
+        let mut line_height = layout.line_height;
+
+        for span in &layout.spans {
+            line_height = span.line_height;
+
+            //Each span wraps within the print area (GS L / GS W) that was set when it was added
+            let width = span.area_width.min(width).max(span.char_width());
+
+            //Positions outside of the line are ignored like they are on the printer
+            if let Some(move_x) = span.move_x {
+                let target = match move_x {
+                    TextMove::Absolute(pos) => pos as i64,
+                    TextMove::Relative(offset) => temp_x as i64 + offset as i64,
+                };
+                if target >= 0 && (target as usize) < width {
+                    temp_x = target as usize;
+                }
+                continue;
+            }
 
-        for span in &mut layout.spans {
             let char_width = span.char_width();
             let words = WordSeparator::UnicodeBreakProperties.find_words(span.text.as_str());
 
@@ -164,12 +353,13 @@ impl ThermalImage {
 
                 if word.word.contains('\n') {
                     lines.push(newline.clone());
+                    line_heights.push(line_height);
                     temp_x = 0;
                     continue;
                 }
 
-                let word_len = word.word.len() + word.whitespace.len();
-                if word_len * char_width < width - temp_x {
+                let word_len = word.word.chars().count() + word.whitespace.chars().count();
+                if word_len * char_width < width.saturating_sub(temp_x) {
                     lines.last_mut().unwrap().push((
                         span,
                         format!("{}{}", word.word, word.whitespace),
@@ -180,10 +370,11 @@ impl ThermalImage {
                     let broken = word.break_apart(width / char_width);
 
                     for broke in broken {
-                        let broke_word_len =
-                            broke.word.len() as f32 + broke.whitespace.len() as f32;
+                        let broke_word_len = broke.word.chars().count() as f32
+                            + broke.whitespace.chars().count() as f32;
                         if width as f32 - (broke_word_len * char_width as f32) < char_width as f32 {
                             lines.push(newline.clone());
+                            line_heights.push(line_height);
                             temp_x = 0;
                             lines.last_mut().unwrap().push((
                                 span,
@@ -191,6 +382,7 @@ impl ThermalImage {
                                 temp_x,
                             ));
                             lines.push(newline.clone());
+                            line_heights.push(line_height);
                         } else {
                             lines.last_mut().unwrap().push((
                                 span,
@@ -203,6 +395,7 @@ impl ThermalImage {
                 } else {
                     //New line and then add word
                     lines.push(newline.clone());
+                    line_heights.push(line_height);
                     temp_x = 0;
                     lines.last_mut().unwrap().push((
                         span,
@@ -214,38 +407,65 @@ impl ThermalImage {
             }
         }
 
+        line_heights.push(line_height);
+
         let mut new_x = x;
         let mut new_y = y;
 
-        for line in lines.into_iter() {
+        //A layout ending in a newline has already advanced past its last line
+        if lines.len() > 1 && lines.last().unwrap().is_empty() {
+            lines.pop();
+            line_heights.pop();
+        }
+
+        for (line, line_height) in lines.into_iter().zip(line_heights) {
             let mut line_height_mult = 1;
             let mut precalculated_width = 0;
             let mut justify = TextJustify::Left;
+            let mut margin_left = 0;
+            let mut area_width = width;
             let mut iter = 0;
 
             for word in &line {
                 if iter == 0 {
                     justify = word.0.justify.clone();
+                    margin_left = word.0.margin_left;
+                    area_width = word.0.area_width.min(width);
                 }
-                precalculated_width += word.1.len() * word.0.char_width();
+                //Words carry their x offset, so the line ends where the last word ends
+                precalculated_width =
+                    precalculated_width.max(word.2 + word.1.chars().count() * word.0.char_width());
                 iter += 1;
             }
+            precalculated_width = precalculated_width.min(area_width);
 
+            new_x = x + margin_left;
             match justify {
-                TextJustify::Center => new_x = (width - precalculated_width) / 2,
-                TextJustify::Right => new_x = width - precalculated_width,
+                TextJustify::Center => new_x += (area_width - precalculated_width) / 2,
+                TextJustify::Right => new_x += area_width - precalculated_width,
                 _ => {}
             }
 
+            let line_x = new_x;
+
+            let mut line_bottom = 0;
+            for word in &line {
+                line_height_mult = line_height_mult.max(word.0.line_mult());
+                line_bottom = line_bottom.max(word.0.paper_height());
+            }
+
+            //Characters of different sizes share the bottom of the line like they do on paper
             for word in &line {
-                if word.0.stretch_height > 1.0 {
-                    line_height_mult = word.0.stretch_height as usize;
-                }
-                let (w, _) = self.render_word(new_x, new_y, word.1.as_str(), word.0);
-                new_x += w;
+                let word_y = new_y + line_bottom - word.0.paper_height();
+                self.render_word(line_x + word.2, word_y, word.1.as_str(), word.0);
+            }
+
+            //Upside-down lines are turned around as a whole across the paper
+            if line.first().is_some_and(|word| word.0.upside_down) {
+                self.flip_pixels(0, new_y, self.width, line_bottom.max(line_height));
             }
             new_x = x;
-            new_y += layout.line_height as usize * line_height_mult;
+            new_y += line_height * line_height_mult;
         }
 
         (new_x, new_y)
@@ -268,18 +488,34 @@ impl ThermalImage {
 
         let baseline = f32::ceil(font_metrics.ascent + font_metrics.descent);
 
+        //The background color fills the character cells behind the characters
+        if let Some(ink) = Ink::from_color(&span.background_color) {
+            self.ink = ink;
+            let width = text.chars().filter(|c| !c.is_control()).count() * span.char_width();
+            self.draw_rect(x, y, width, span.paper_height());
+            self.ink = Ink::First;
+        }
+
         //Need a solution for graphemes maybe
         for char in text.chars() {
             let (metrics, bitmap) = font.rasterize(char, span.size as f32);
 
-            let mut bitmap = bitmap;
-            bitmap = self.scale_bitmap(
-                &bitmap,
-                metrics.width,
-                metrics.height,
-                span.stretch_width as usize,
-                span.stretch_height as usize,
-            );
+            let bitmap = match span.smoothing {
+                true => self.smooth_bitmap(
+                    &bitmap,
+                    metrics.width,
+                    metrics.height,
+                    span.stretch_width as usize,
+                    span.stretch_height as usize,
+                ),
+                false => self.scale_bitmap(
+                    &bitmap,
+                    metrics.width,
+                    metrics.height,
+                    span.stretch_width as usize,
+                    span.stretch_height as usize,
+                ),
+            };
 
             let glyph_index = font.lookup_glyph_index(char);
             let char_data = CharacterData::classify(char, glyph_index);
@@ -296,23 +532,62 @@ impl ThermalImage {
             let y_offset =
                 f32::ceil((baseline - metrics.bounds.height) + (-1.0 * metrics.bounds.ymin))
                     as usize;
+
+            //The character cell is turned a quarter clockwise in place
+            if span.rotated {
+                let cell_width = span.glyph_width();
+                let cell_height = span.cell_height() * span.stretch_height as usize;
+                let glyph_width = metrics.width * span.stretch_width as usize;
+                let glyph_height = metrics.height * span.stretch_height as usize;
+                let glyph_x = metrics.bounds.xmin.round().abs() as usize;
+                let glyph_y = y_offset * span.stretch_height as usize;
+
+                let mut cell = vec![0u8; cell_width * cell_height];
+                for gy in 0..glyph_height.min(cell_height.saturating_sub(glyph_y)) {
+                    for gx in 0..glyph_width.min(cell_width.saturating_sub(glyph_x)) {
+                        cell[(glyph_y + gy) * cell_width + glyph_x + gx] =
+                            bitmap[gy * glyph_width + gx];
+                    }
+                }
+
+                let mut rotated = Vec::<u8>::with_capacity(cell.len());
+                for ry in 0..cell_width {
+                    for rx in 0..cell_height {
+                        rotated.push(cell[(cell_height - 1 - rx) * cell_width + ry]);
+                    }
+                }
+
+                self.put_glyph(cur_x, y, cell_height, cell_width, rotated, span);
+                cur_x += span.char_width();
+                w += span.char_width();
+                continue;
+            }
+
             let x_offset = cur_x + metrics.bounds.xmin.round().abs() as usize;
 
-            self.put_pixels(
+            self.put_glyph(
                 x_offset,
                 y + y_offset * span.stretch_height as usize,
                 metrics.width * span.stretch_width as usize,
                 metrics.height * span.stretch_height as usize,
                 bitmap,
-                true,
-                true,
+                span,
             );
             cur_x += span.char_width();
             w += span.char_width();
             h = h.max((metrics.height * span.stretch_height as usize) + y_offset);
         }
 
-        if span.underline > 0 {
+        if span.rotated {
+            h = span.paper_height();
+        }
+
+        //Lines and inverted areas are printed in the character color
+        let char_ink = Ink::from_color(&span.color);
+        self.ink = char_ink.unwrap_or(Ink::First);
+
+        //Rotated characters are not underlined
+        if span.underline > 0 && !span.rotated && char_ink.is_some() {
             let under_y = (y
                 + (font_metrics.ascent * span.stretch_height) as usize
                 + span.underline) as usize;
@@ -321,7 +596,7 @@ impl ThermalImage {
             self.draw_rect(under_x, under_y, w, span.underline);
         }
 
-        if span.strikethrough > 0 {
+        if span.strikethrough > 0 && char_ink.is_some() {
             let strike_y = y + ((font_metrics.ascent * span.stretch_height) / 2.0) as usize;
             let strike_x = x;
 
@@ -337,11 +612,37 @@ impl ThermalImage {
             self.invert_pixels(x, y, w, h);
         }
 
-        if span.upside_down {
-            self.flip_pixels(x, y, w, h);
+        self.ink = Ink::First;
+        (w, h)
+    }
+
+    //Puts a character bitmap in the character color with its shading and emphasis
+    fn put_glyph(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        bitmap: Vec<u8>,
+        span: &TextSpan,
+    ) {
+        //Shading is printed behind the character, one dot down and to the right
+        if let Some(ink) = Ink::from_color(&span.shadow_color) {
+            self.ink = ink;
+            self.put_pixels(x + 1, y + 1, width, height, bitmap.clone(), true, true);
         }
 
-        (w, h)
+        if let Some(ink) = Ink::from_color(&span.color) {
+            self.ink = ink;
+
+            //Emphasized dots are printed twice, the second one dot to the right
+            if span.bold {
+                self.put_pixels(x + 1, y, width, height, bitmap.clone(), true, true);
+            }
+            self.put_pixels(x, y, width, height, bitmap, true, true);
+        }
+
+        self.ink = Ink::First;
     }
 
     pub fn scale_bitmap(
@@ -375,6 +676,56 @@ impl ThermalImage {
         scaled
     }
 
+    //Scales with bilinear sampling of the glyph coverage before the threshold
+    //so enlarged characters get smooth edges instead of stair steps (GS b)
+    pub fn smooth_bitmap(
+        &mut self,
+        bitmap: &Vec<u8>,
+        width: usize,
+        height: usize,
+        stretch_width: usize,
+        stretch_height: usize,
+    ) -> Vec<u8> {
+        let sw = width * stretch_width;
+        let sh = height * stretch_height;
+
+        let mut scaled = Vec::with_capacity(sw * sh);
+
+        //Coverage outside of the glyph is empty
+        let sample = |x: i64, y: i64| -> f32 {
+            if x < 0 || y < 0 || x >= width as i64 || y >= height as i64 {
+                return 0.0;
+            }
+            bitmap[width * y as usize + x as usize] as f32
+        };
+
+        for y in 0..sh {
+            let src_y = (y as f32 + 0.5) / stretch_height as f32 - 0.5;
+            let y0 = src_y.floor();
+            let fy = src_y - y0;
+
+            for x in 0..sw {
+                let src_x = (x as f32 + 0.5) / stretch_width as f32 - 0.5;
+                let x0 = src_x.floor();
+                let fx = src_x - x0;
+                let (x0, y0) = (x0 as i64, y0 as i64);
+
+                let top = sample(x0, y0) * (1.0 - fx) + sample(x0 + 1, y0) * fx;
+                let bottom = sample(x0, y0 + 1) * (1.0 - fx) + sample(x0 + 1, y0 + 1) * fx;
+                let coverage = top * (1.0 - fy) + bottom * fy;
+
+                let pixel = if coverage < SCALE_THRESHOLD as f32 {
+                    0
+                } else {
+                    255
+                };
+                scaled.push(pixel)
+            }
+        }
+
+        scaled
+    }
+
     pub fn invert_pixels(&mut self, x: usize, y: usize, width: usize, height: usize) {
         if x + width > self.width {
             return;
@@ -388,6 +739,7 @@ impl ThermalImage {
             let idx = cur_y * self.width + cur_x;
             for i in 0..width {
                 self.bytes[idx + i] = 255 - self.bytes[idx + i];
+                self.inks[idx + i] = self.ink;
                 cur_x += 1;
             }
             cur_x = x;
@@ -401,18 +753,74 @@ impl ThermalImage {
         };
         self.ensure_height(y + height);
 
-        let mut sub_image = Vec::<u8>::with_capacity(width * height);
+        let (mut sub_image, mut sub_inks) = self.copy_rect(x, y, width, height);
+        sub_image.reverse();
+        sub_inks.reverse();
+        self.paste_rect(x, y, width, height, &sub_image, &sub_inks);
+    }
 
-        for cur_y in y..y + height {
-            let idx = cur_y * self.width + x;
-            for cur_x in x..width {
-                sub_image.push(self.bytes[idx + cur_x]);
+    //Turns an area around the center of the paper, it ends up mirrored
+    //to the other side like it would be as part of an upside-down line
+    pub fn flip_line_pixels(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        if x + width > self.width {
+            return;
+        };
+        self.flip_pixels(x, y, width, height);
+
+        let to_x = self.width - x - width;
+        if to_x == x {
+            return;
+        }
+
+        let (sub_image, sub_inks) = self.copy_rect(x, y, width, height);
+        self.clear_rect(x, y, width, height);
+
+        let (dest_image, mut dest_inks) = self.copy_rect(to_x, y, width, height);
+        let mut merged = Vec::<u8>::with_capacity(width * height);
+        for i in 0..width * height {
+            if sub_image[i] < dest_image[i] {
+                dest_inks[i] = sub_inks[i];
             }
+            merged.push(sub_image[i].min(dest_image[i]));
         }
+        self.paste_rect(to_x, y, width, height, &merged, &dest_inks);
+    }
 
-        sub_image.reverse();
+    //Area of the image with the ink of each pixel, the area has to be inside the image
+    fn copy_rect(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+    ) -> (Vec<u8>, Vec<Ink>) {
+        self.ensure_height(y + height);
+        let mut sub_image = Vec::<u8>::with_capacity(width * height);
+        let mut sub_inks = Vec::<Ink>::with_capacity(width * height);
+        for cur_y in y..y + height {
+            let idx = cur_y * self.width + x;
+            sub_image.extend_from_slice(&self.bytes[idx..idx + width]);
+            sub_inks.extend_from_slice(&self.inks[idx..idx + width]);
+        }
+        (sub_image, sub_inks)
+    }
 
-        self.put_pixels(x, y, width, height, sub_image, false, false);
+    fn paste_rect(
+        &mut self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        pixels: &[u8],
+        inks: &[Ink],
+    ) {
+        self.ensure_height(y + height);
+        for cur_y in 0..height {
+            let idx = (y + cur_y) * self.width + x;
+            self.bytes[idx..idx + width]
+                .copy_from_slice(&pixels[cur_y * width..(cur_y + 1) * width]);
+            self.inks[idx..idx + width].copy_from_slice(&inks[cur_y * width..(cur_y + 1) * width]);
+        }
     }
 
     pub fn put_pixels(
@@ -428,7 +836,8 @@ impl ThermalImage {
         let mut cur_x = x;
         let mut cur_y = y;
 
-        if x + width > self.width {
+        //Pixels past the right edge are clipped, glyphs can overhang their advance
+        if x >= self.width {
             return false;
         };
 
@@ -445,11 +854,14 @@ impl ThermalImage {
                 //ensure black or white only
                 let pixel = if pixel < THRESHOLD { 0 } else { 255 };
 
-                self.bytes[idx] = if invert {
-                    u8::min(255 - pixel, self.bytes[idx])
-                } else {
-                    u8::min(pixel, self.bytes[idx])
-                };
+                if cur_x < self.width {
+                    let pixel = if invert { 255 - pixel } else { pixel };
+                    //The last ink printed on a dot is the one that shows
+                    if pixel == 0 {
+                        self.inks[idx] = self.ink;
+                    }
+                    self.bytes[idx] = u8::min(pixel, self.bytes[idx]);
+                }
                 if cur_x == x + width - 1 {
                     cur_x = x;
                     cur_y += 1;
@@ -460,7 +872,10 @@ impl ThermalImage {
         } else {
             for pixel in pixels {
                 let idx = cur_y * self.width + cur_x;
-                self.bytes[idx] = if invert { 255 - pixel } else { pixel };
+                if cur_x < self.width {
+                    self.bytes[idx] = if invert { 255 - pixel } else { pixel };
+                    self.inks[idx] = self.ink;
+                }
                 if cur_x == x + width - 1 {
                     cur_x = x;
                     cur_y += 1;
@@ -483,11 +898,14 @@ impl ThermalImage {
 
         for _ in 0..to_add {
             self.bytes.push(255u8);
+            self.inks.push(Ink::First);
         }
     }
 
     pub fn add_top_margin(&mut self, height: usize) {
         self.bytes.splice(0..0, vec![255u8; self.width * height]);
+        self.inks
+            .splice(0..0, vec![Ink::First; self.width * height]);
     }
 
     pub fn expand_to_width(&mut self, new_width: usize) {
@@ -511,17 +929,23 @@ impl ThermalImage {
         for _ in 0..height {
             self.bytes
                 .splice(insert_idx..insert_idx, left_bytes.clone());
+            self.inks
+                .splice(insert_idx..insert_idx, vec![Ink::First; left]);
             insert_idx += left + old_width;
 
             self.bytes
                 .splice(insert_idx..insert_idx, right_bytes.clone());
+            self.inks
+                .splice(insert_idx..insert_idx, vec![Ink::First; right]);
             insert_idx += right;
         }
 
         self.width = new_width;
     }
 
-    pub fn save_png(&self, filepath: String) {
+    //Saved as grayscale unless the second ink was used, that is saved
+    //as color with the given rgb
+    pub fn save_png(&self, filepath: String, second_ink: [u8; 3]) {
         if self.bytes.len() == 0 || self.width == 0 {
             println!("Nothing to save!");
             return;
@@ -534,10 +958,30 @@ impl ThermalImage {
             self.width as u32,
             self.bytes.len() as u32 / self.width as u32,
         );
-        encoder.set_color(png::ColorType::Grayscale);
         encoder.set_depth(BitDepth::Eight);
 
+        if !self.inks.contains(&Ink::Second) {
+            encoder.set_color(png::ColorType::Grayscale);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&self.bytes).unwrap(); // Save
+            return;
+        }
+
+        let mut rgb = Vec::<u8>::with_capacity(self.bytes.len() * 3);
+        for (pixel, ink) in self.bytes.iter().zip(&self.inks) {
+            match ink {
+                Ink::First => rgb.extend_from_slice(&[*pixel; 3]),
+                Ink::Second => {
+                    let darkness = (255 - *pixel) as f32 / 255.0;
+                    for channel in second_ink {
+                        rgb.push((255.0 - darkness * (255 - channel) as f32) as u8);
+                    }
+                }
+            }
+        }
+
+        encoder.set_color(png::ColorType::Rgb);
         let mut writer = encoder.write_header().unwrap();
-        writer.write_image_data(&self.bytes).unwrap(); // Save
+        writer.write_image_data(&rgb).unwrap(); // Save
     }
 }
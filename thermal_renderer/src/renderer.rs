@@ -1,30 +1,53 @@
 use thermal_parser::command::{Command, CommandType, DeviceCommand};
-use thermal_parser::context::{Context, HumanReadableInterface};
-use thermal_parser::graphics::GraphicsCommand;
+use thermal_parser::context::{Color, Context, HumanReadableInterface, LineBufferItem};
+use thermal_parser::graphics::{stretch_pixels, GraphicsCommand};
 
 pub trait CommandRenderer {
     //default implementation
     fn process_command(&mut self, context: &mut Context, command: &Command) {
         println!("{}", command.handler.debug(command, context));
+
+        //Offline printers only process real-time commands, the rest waits in the buffer
+        let is_held = context.printer_state.is_offline() || !self.held_commands().is_empty();
+        if command.kind != CommandType::RealTime && is_held {
+            self.held_commands().push(command.clone());
+            self.process_held_commands(context);
+            return;
+        }
+        let (start_y, was_page_mode) = (context.graphics.y, context.is_page_mode);
+
         match command.kind {
             CommandType::Text => {
                 let maybe_text = command.handler.get_text(command, context);
+                command.handler.apply_context(command, context);
                 if let Some(text) = maybe_text {
-                    self.draw_text(context, text);
+                    if context.emulate_line_buffer {
+                        self.buffer_text(context, text);
+                    } else {
+                        self.print_text(context, text);
+                    }
                 }
             }
             CommandType::Graphics => {
+                self.print_line_buffer(context);
                 let maybe_gfx = command.handler.get_graphics(command, context);
+                command.apply_graphics(context);
 
                 if let Some(gfx) = maybe_gfx {
                     match gfx {
                         GraphicsCommand::Code2D(code_2d) => {
+                            context.graphics.y = page_top(
+                                context,
+                                (code_2d.points.len() as u32 / code_2d.width.max(1))
+                                    * code_2d.point_height,
+                            );
                             self.begin_graphics(context);
 
                             let mut i = 1;
-                            let origin_x = context.graphics_x_offset(
-                                (code_2d.points.len() * code_2d.point_width as usize) as u32,
-                            ) as usize;
+                            let origin_x = context
+                                .graphics_x_offset(code_2d.width * code_2d.point_width)
+                                as usize;
+                            context.graphics.x = origin_x;
 
                             for p in code_2d.points {
                                 if i != 1 && i % code_2d.width == 1 {
@@ -55,6 +78,7 @@ pub trait CommandRenderer {
                                 _ => {}
                             }
 
+                            context.graphics.y = page_top(context, barcode.point_height as u32);
                             self.begin_graphics(context);
 
                             context.graphics.x = context.graphics_x_offset(
@@ -86,20 +110,42 @@ pub trait CommandRenderer {
                             }
                         }
                         GraphicsCommand::Image(image) => {
+                            let (width, height) = image.stretched_size();
                             if image.advances_xy {
-                                context.graphics.x =
-                                    context.graphics_x_offset(image.width) as usize;
+                                context.graphics.x = context.graphics_x_offset(width) as usize;
                             }
-                            self.draw_image(
-                                context,
-                                image.as_grayscale(),
+                            let y = context.graphics.y;
+                            context.graphics.y = page_top(context, height);
+                            let pixels = stretch_pixels(
+                                &image.as_grayscale(),
                                 image.width as usize,
-                                image.height as usize,
+                                image.stretch,
                             );
+                            match image.as_colors() {
+                                Some(colors) => self.draw_color_image(
+                                    context,
+                                    pixels,
+                                    stretch_pixels(&colors, image.width as usize, image.stretch),
+                                    width as usize,
+                                    height as usize,
+                                ),
+                                None => self.draw_image(
+                                    context,
+                                    pixels,
+                                    width as usize,
+                                    height as usize,
+                                ),
+                            }
                             if image.advances_xy {
                                 context.graphics.x = 0;
-                                context.graphics.y += image.height as usize;
+                                context.graphics.y += height as usize;
                                 context.graphics.y += context.line_height_pixels() as usize;
+                            } else {
+                                context.graphics.x += width as usize;
+                                //Drawing flushes pending text in standard mode, keep the line it moved to
+                                if context.is_page_mode {
+                                    context.graphics.y = y;
+                                }
                             }
                         }
                         GraphicsCommand::Rectangle(_) => {}
@@ -113,19 +159,106 @@ pub trait CommandRenderer {
             CommandType::ContextControl => {
                 command.handler.apply_context(command, context);
 
+                //ESC @ has already discarded the line buffer
+                self.print_line_buffer(context);
                 self.handle_device_commands(
                     &command.handler.get_device_command(command, context),
                     context,
                 );
             }
+            //Real-time commands don't print
+            CommandType::RealTime => {
+                let device_commands = command.handler.get_device_command(command, context);
+                command.handler.apply_context(command, context);
+                self.handle_device_commands(&device_commands, context);
+            }
             CommandType::Control => {
-                self.handle_device_commands(
-                    &command.handler.get_device_command(command, context),
-                    context,
-                );
+                let device_commands = command.handler.get_device_command(command, context);
+                match &device_commands {
+                    //Horizontal moves are part of the line in the buffer
+                    Some(moves) if context.emulate_line_buffer && is_line_move(moves) => {
+                        for device_command in moves {
+                            context
+                                .line_buffer
+                                .push(LineBufferItem::Move(device_command.clone()));
+                        }
+                    }
+                    _ => {
+                        self.print_line_buffer(context);
+                        self.handle_device_commands(&device_commands, context);
+                    }
+                }
+                command.handler.apply_context(command, context);
             }
             _ => {}
         }
+
+        //Printed dot rows use up paper, page mode content uses it once the page is printed
+        if !was_page_mode && !context.is_page_mode && context.graphics.y > start_y {
+            context.feed_paper((context.graphics.y - start_y) as u32);
+        }
+
+        if command.kind == CommandType::RealTime {
+            self.process_held_commands(context);
+        }
+    }
+
+    fn print_text(&mut self, context: &mut Context, text: String) {
+        let ends_line = text.contains('\n');
+        self.draw_text(context, text);
+        if ends_line {
+            context.graphics.x = 0;
+        }
+    }
+
+    //Text waits in the line buffer until a line feed or another
+    //printing command prints the whole line at once
+    fn buffer_text(&mut self, context: &mut Context, text: String) {
+        let (line, rest) = match text.rfind('\n') {
+            Some(i) => text.split_at(i + 1),
+            None => ("", text.as_str()),
+        };
+        if !line.is_empty() {
+            let item = LineBufferItem::Text(context.text.clone(), line.to_string());
+            context.line_buffer.push(item);
+            self.print_line_buffer(context);
+        }
+        if !rest.is_empty() {
+            let item = LineBufferItem::Text(context.text.clone(), rest.to_string());
+            context.line_buffer.push(item);
+        }
+    }
+
+    //Each piece of text is drawn with its own style, the line spacing
+    //is the one set when the line is printed
+    fn print_line_buffer(&mut self, context: &mut Context) {
+        if context.line_buffer.is_empty() {
+            return;
+        }
+        let text_context = context.text.clone();
+        for item in std::mem::take(&mut context.line_buffer) {
+            match item {
+                LineBufferItem::Text(style, text) => {
+                    context.text = style;
+                    context.text.line_spacing = text_context.line_spacing;
+                    self.print_text(context, text);
+                    context.text = text_context.clone();
+                }
+                LineBufferItem::Move(device_command) => {
+                    self.handle_device_commands(&Some(vec![device_command]), context);
+                }
+            }
+        }
+    }
+
+    //Commands held while offline are processed in order once the printer is back online
+    fn process_held_commands(&mut self, context: &mut Context) {
+        if context.printer_state.is_offline() {
+            return;
+        }
+        for command in std::mem::take(self.held_commands()) {
+            self.process_command(context, &command);
+        }
     }
 
     fn handle_device_commands(
@@ -140,17 +273,38 @@ pub trait CommandRenderer {
                 match device_command {
                     DeviceCommand::BeginPrint => self.begin_render(context),
                     DeviceCommand::EndPrint => self.end_render(context),
-                    DeviceCommand::FeedLine(num_lines) => {
-                        context.graphics.y +=
-                            context.line_height_pixels() as usize * *num_lines as usize;
+                    DeviceCommand::FeedLine(_) | DeviceCommand::Feed(_) => {
+                        context.graphics.x = 0;
+                        if let Some(offset) = feed_pixels(context, device_command) {
+                            context.graphics.y_max = context.graphics.y_max.max(context.graphics.y);
+                            context.graphics.y = (context.graphics.y as i64 + offset) as usize;
+                        }
+                    }
+                    DeviceCommand::MoveY(n) => {
+                        context.graphics.y = (*n as u32 * context.motion_unit_y_pixels()) as usize;
                     }
-                    DeviceCommand::Feed(num) => {
-                        context.graphics.y +=
-                            context.motion_unit_y_pixels() as usize * *num as usize;
+                    DeviceCommand::MoveYRelative(n) => {
+                        let y = context.graphics.y as i64
+                            + *n as i64 * context.motion_unit_y_pixels() as i64;
+                        context.graphics.y = y.max(0) as usize;
+                    }
+                    //Form feed leaves page mode once the page has been printed
+                    DeviceCommand::EndPage => {
+                        context.is_page_mode = false;
                     }
                     DeviceCommand::FullCut | DeviceCommand::PartialCut => {
+                        let mut state = context.printer_state.clone();
+                        state.cut();
+                        context.set_printer_state(state);
                         context.graphics.y += context.line_height_pixels() as usize * 2;
                     }
+                    DeviceCommand::Pulse { pin, .. } => {
+                        let mut state = context.printer_state.clone();
+                        state.kick_drawer(*pin);
+                        context.set_printer_state(state);
+                    }
+                    DeviceCommand::Transmit(bytes) => context.transmit(bytes),
+                    DeviceCommand::Cancel => self.held_commands().clear(),
                     _ => {}
                 }
             }
@@ -162,7 +316,59 @@ pub trait CommandRenderer {
     fn draw_rect(&mut self, context: &mut Context, w: usize, h: usize);
     fn end_graphics(&mut self, context: &mut Context);
     fn draw_image(&mut self, context: &mut Context, bytes: Vec<u8>, width: usize, height: usize);
+    //Images printed with more than one color, colors holds the color of each pixel
+    fn draw_color_image(
+        &mut self,
+        context: &mut Context,
+        bytes: Vec<u8>,
+        _colors: Vec<Color>,
+        width: usize,
+        height: usize,
+    ) {
+        self.draw_image(context, bytes, width, height);
+    }
     fn draw_text(&mut self, context: &mut Context, text: String);
     fn draw_device_command(&mut self, context: &mut Context, command: &DeviceCommand);
     fn end_render(&mut self, context: &mut Context);
+    //Commands received while the printer is offline wait here until it's back online
+    fn held_commands(&mut self) -> &mut Vec<Command>;
+}
+
+fn is_line_move(device_commands: &[DeviceCommand]) -> bool {
+    device_commands.iter().all(|device_command| {
+        matches!(
+            device_command,
+            DeviceCommand::MoveX(_) | DeviceCommand::MoveXRelative(_)
+        )
+    })
+}
+
+//In page mode the print position is the bottom of what is printed next,
+//content that would reach above the print area starts at its top
+pub fn page_top(context: &Context, height: u32) -> usize {
+    if !context.is_page_mode {
+        return context.graphics.y;
+    }
+    context.graphics.y.saturating_sub(height as usize)
+}
+
+//Distance in pixels that a feed moves the paper. Reverse feeds
+//stop at the reverse feed limit behind the furthest fed position
+pub fn feed_pixels(context: &Context, device_command: &DeviceCommand) -> Option<i64> {
+    let pixels = match device_command {
+        DeviceCommand::Feed(n) => *n as i64 * context.motion_unit_y_pixels() as i64,
+        DeviceCommand::FeedLine(n) => *n as i64 * context.line_height_pixels() as i64,
+        _ => return None,
+    };
+
+    if pixels >= 0 {
+        return Some(pixels);
+    }
+
+    let y = context.graphics.y as i64;
+    let furthest_y = context.graphics.y_max.max(context.graphics.y) as i64;
+    let limit = context.graphics.reverse_feed_max * context.motion_unit_y_pixels();
+    let limit_y = (furthest_y - limit as i64).max(0);
+
+    Some(((y + pixels).max(limit_y) - y).min(0))
 }
@@ -1,6 +1,11 @@
+use std::cell::RefCell;
 use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::Arc;
 use thermal_parser::command::Command;
-use thermal_parser::context::Context;
+use thermal_parser::context::{Context, PrinterState};
+use thermal_parser::parser::Parser;
+use thermal_parser::response::ResponseBuffer;
 use thermal_renderer::html_renderer::HtmlRenderer;
 use thermal_renderer::image_renderer::ImageRenderer;
 use thermal_renderer::renderer::CommandRenderer;
@@ -45,11 +50,316 @@ fn thick_barcode() {
     it_renders("thick_barcode.bin");
 }
 
+#[test]
+fn page_mode_demo() {
+    it_renders("page_mode_demo.bin");
+}
+
 #[test]
 fn oiap4md() {
     it_renders_image("test.esc");
 }
 
+#[test]
+fn it_transmits_responses_to_the_sink() {
+    let renderer = ImageRenderer::new(get_out_path("responses"));
+    let (mut command_parser, _, responses) = response_parser(renderer, Context::new());
+
+    //NV graphics capacity
+    command_parser.parse_bytes(&vec![0x1D, b'(', b'L', 2, 0, 48, 48, 0x0A]);
+
+    assert_eq!(
+        responses.take(),
+        [&[0x37, 0x30][..], b"262144", &[0]].concat()
+    );
+}
+
+#[test]
+fn it_simulates_printer_faults() {
+    let mut context = Context::new();
+    context.printer_state = PrinterState {
+        paper_roll_rows: Some(1000),
+        paper_near_end_rows: 500,
+        asb_enabled: 0b1111,
+        ..Default::default()
+    };
+    let renderer = ImageRenderer::new(get_out_path("faults"));
+    let (mut command_parser, context, responses) = response_parser(renderer, context);
+
+    //The near-end sensor stops printing, what follows waits for new paper
+    let feed = [0x1B, b'c', 4, 1, 0x1B, b'J', 255, 0x1B, b'J', 255];
+    command_parser.parse_bytes(&[&feed[..], b"held\n"].concat());
+    assert_eq!(responses.take(), vec![0x18, 0x00, 0x03, 0x00]);
+
+    let mut state = context.borrow().printer_state.clone();
+    state.load_paper(Some(1000));
+    context.borrow_mut().set_printer_state(state);
+    assert_eq!(responses.take(), vec![0x10, 0x00, 0x00, 0x00]);
+    assert_eq!(context.borrow().printer_state.paper_rows_used, 0);
+
+    command_parser.parse_bytes(&vec![0x1D, b'r', 1, 0x0A]);
+    assert_eq!(responses.take(), vec![0x00]);
+    assert!(context.borrow().printer_state.paper_rows_used > 0);
+
+    //A jammed cutter can't be recovered until the jam is cleared
+    context.borrow_mut().printer_state.cutter_jammed = true;
+    let cut = [0x1D, b'V', 0];
+    let lost_feed = [0x1B, b'J', 100];
+    let recover = [0x10, 0x05, 1, 0x0A];
+    command_parser.parse_bytes(&[&cut[..], &lost_feed, &recover].concat());
+    assert_eq!(responses.take(), vec![0x18, 0x08, 0x00, 0x00]);
+    assert!(context.borrow().printer_state.autocutter_error);
+
+    //Clearing the buffers drops the held feed instead of printing it
+    let held_rows = context.borrow().printer_state.paper_rows_used;
+    context.borrow_mut().printer_state.cutter_jammed = false;
+    command_parser.parse_bytes(&vec![0x10, 0x05, 2]);
+    assert_eq!(responses.take(), vec![0x10, 0x00, 0x00, 0x00]);
+    assert_eq!(context.borrow().printer_state.paper_rows_used, held_rows);
+}
+
+#[test]
+fn it_prints_page_content_only_when_the_page_is_printed() {
+    //Leaving page mode without FF or ESC FF discards the page
+    let discarded = [
+        &[0x1B, b'@', 0x1B, b'L'][..],
+        b"Composed\n",
+        &[0x1B, b'S', 0x1B, b'@'],
+    ]
+    .concat();
+    let printed = [
+        &[0x1B, b'@', 0x1B, b'L'][..],
+        b"Composed\n",
+        &[0x0C, 0x1B, b'@'],
+    ]
+    .concat();
+
+    assert!(!render_html("page_discarded", Context::new(), &discarded).contains("Composed"));
+    assert!(render_html("page_printed", Context::new(), &printed).contains("Composed"));
+
+    render_image("page_discarded", Context::new(), &discarded);
+    render_image("page_printed", Context::new(), &printed);
+    assert!(is_blank(&read_png("page_discarded")));
+    assert!(!is_blank(&read_png("page_printed")));
+}
+
+#[test]
+fn it_cancels_the_page_region() {
+    //CAN drops what was composed in the print area so far, the position is kept
+    let cancelled = [
+        &[0x1B, b'@', 0x1B, b'L'][..],
+        b"Dropped\n",
+        &[0x18],
+        b"Kept\n",
+        &[0x0C, 0x1B, b'@'],
+    ]
+    .concat();
+    let blank_line = [
+        &[0x1B, b'@', 0x1B, b'L'][..],
+        b"       \n",
+        b"Kept\n",
+        &[0x0C, 0x1B, b'@'],
+    ]
+    .concat();
+
+    let html = render_html("page_cancelled", Context::new(), &cancelled);
+    assert!(!html.contains("Dropped") && html.contains("Kept"));
+
+    render_image("page_cancelled", Context::new(), &cancelled);
+    render_image("page_blank_line", Context::new(), &blank_line);
+    assert!(read_png("page_cancelled") == read_png("page_blank_line"));
+}
+
+#[test]
+fn it_uses_paper_for_printed_pages() {
+    assert_page_paper(ImageRenderer::new(get_out_path("page_paper")));
+    assert_page_paper(HtmlRenderer::new(get_out_path("page_paper")));
+}
+
+#[test]
+fn it_opens_the_cash_drawer() {
+    let mut context = Context::new();
+    context.printer_state.asb_enabled = 0b0001;
+    let renderer = HtmlRenderer::new(get_out_path("drawer"));
+    let (mut command_parser, context, responses) = response_parser(renderer, context);
+
+    //A kick on pin 5 doesn't reach the drawer on pin 2
+    let pin5 = [0x1B, b'p', 1, 25, 250];
+    let pin2 = [0x1B, b'p', 0, 25, 250];
+    let status = [0x10, 0x04, 1, 0x1D, b'r', 2, 0x0A];
+    command_parser.parse_bytes(&[&[0x1B, b'@'], &b"Total\n"[..], &pin5, &pin2, &status].concat());
+
+    assert_eq!(responses.take(), vec![0x14, 0x00, 0x00, 0x00, 0x16, 0x01]);
+    let html = std::fs::read_to_string(format!("{}.html", get_out_path("drawer"))).unwrap();
+    assert!(html.contains("Drawer kick pin 5 (50ms on, 500ms off)"));
+    assert!(html.contains("Drawer kick pin 2 (50ms on, 500ms off)"));
+
+    //Closing the drawer is sent back too
+    let state = context.borrow().printer_state.clone();
+    context.borrow_mut().set_printer_state(PrinterState {
+        drawer_pin_high: false,
+        ..state
+    });
+    assert_eq!(responses.take(), vec![0x10, 0x00, 0x00, 0x00]);
+}
+
+#[test]
+fn it_limits_reverse_feeds() {
+    //ESC K and ESC e stop 60 motion units behind the furthest fed position
+    let feeds = [0x1B, b'J', 100, 0x1B, b'K', 255, 0x1B, b'e', 5];
+    let positions = render_positions("reverse_feed_limit", &[&[0x1B, b'@'][..], &feeds].concat());
+    assert_eq!(y_after(&positions, "Print and Reverse Feed"), vec![40]);
+    assert_eq!(
+        y_after(&positions, "Print and Reverse Feed Lines"),
+        vec![40]
+    );
+
+    //The limit follows the vertical motion unit
+    let motion_units = [0x1B, b'@', 0x1D, b'P', 0, 105];
+    let positions = render_positions("reverse_feed_limit", &[&motion_units[..], &feeds].concat());
+    assert_eq!(y_after(&positions, "Print and Reverse Feed"), vec![80]);
+}
+
+#[test]
+fn it_overprints_after_reverse_feeding() {
+    render_positions("single_line", &[&[0x1B, b'@'][..], b"ABC\n"].concat());
+    let reverse_feed = [0x1B, b'e', 1];
+    let overprint = [&[0x1B, b'@'][..], b"ABC", &reverse_feed, b"XYZ\n"].concat();
+    render_positions("overprinted_line", &overprint);
+
+    //The second line is composited over the first one
+    let single = read_png("single_line");
+    let overprinted = read_png("overprinted_line");
+    assert_eq!(single.len(), overprinted.len());
+    assert!(single
+        .iter()
+        .zip(&overprinted)
+        .all(|(a, b)| *a > 127 || *b <= 127));
+    assert!(single != overprinted);
+}
+
+#[test]
+fn it_prints_the_line_buffer_as_a_whole() {
+    //Justification is ignored mid-line, the line spacing is the one set when the line prints
+    let mid_line = [
+        &[0x1B, b'@'][..],
+        b"Left ",
+        &[0x1D, b'!', 0x11],
+        b"BIG",
+        &[0x1D, b'!', 0x00, 0x1B, b'a', 1, 0x1B, b'3', 60],
+        b" small\nNext\n",
+    ]
+    .concat();
+    let line_start = [
+        &[0x1B, b'@', 0x1B, b'3', 60][..],
+        b"Left ",
+        &[0x1D, b'!', 0x11],
+        b"BIG",
+        &[0x1D, b'!', 0x00],
+        b" small\nNext\n",
+    ]
+    .concat();
+
+    let mut context = Context::new();
+    context.emulate_line_buffer = true;
+
+    let html = render_html("line_buffer_mid_line", context.clone(), &mid_line);
+    let line = &html[html.find("Left").unwrap()..html.find("Next").unwrap()];
+    assert!(line.contains("BIG") && !line.contains("class='cnt"));
+    assert_eq!(
+        html,
+        render_html("line_buffer_line_start", context.clone(), &line_start)
+    );
+
+    render_image("line_buffer_mid_line", context.clone(), &mid_line);
+    render_image("line_buffer_line_start", context, &line_start);
+    assert!(read_png("line_buffer_mid_line") == read_png("line_buffer_line_start"));
+}
+
+#[test]
+fn it_spaces_rotated_lines_by_the_character_width() {
+    //Double width rotated characters take up twice the line spacing
+    let rotated = [
+        0x1B, b'@', 0x1B, b'V', 1, 0x1D, b'!', 0x10, b'A', b'B', b'\n',
+    ];
+    let html = render_html("rotated_double_width", Context::new(), &rotated);
+    let line_height = 30.0 * (152.0f32 / 210.0);
+    assert!(html.contains(&format!(
+        "line-height: {}px; transform: scaleY(2);",
+        line_height * 2.0
+    )));
+    assert!(html.contains("<span class='rt' style='margin-right: "));
+    assert!(html.contains(">A</span>"));
+}
+
+#[test]
+fn it_turns_images_around_with_upside_down_lines() {
+    //A raster image 8 dots wide with 2 dots on the top row and 1 below
+    let image = [0x1D, b'v', b'0', 0, 1, 0, 2, 0, 0b1100_0000, 0b1000_0000];
+    let upright = [&[0x1B, b'@'][..], &image, b"\n"].concat();
+    let upside_down = [&[0x1B, b'@', 0x1B, b'{', 1][..], &image, b"\n"].concat();
+
+    assert!(!render_html("image_upright", Context::new(), &upright).contains("class='upd'"));
+    assert!(
+        render_html("image_upside_down", Context::new(), &upside_down)
+            .contains("<div class='upd'><img")
+    );
+
+    render_image("image_upright", Context::new(), &upright);
+    render_image("image_upside_down", Context::new(), &upside_down);
+    let width = read_png_frame("image_upright").0.width as usize;
+    let upright = dark_pixels("image_upright");
+    let top = upright.iter().map(|(_, y)| *y).min().unwrap();
+    let bottom = upright.iter().map(|(_, y)| *y).max().unwrap();
+    assert!(upright.iter().all(|(x, _)| *x < width / 2));
+
+    //Turned around the center of the paper the image ends up on the right
+    let mut turned = upright
+        .iter()
+        .map(|(x, y)| (width - 1 - x, top + bottom - y))
+        .collect::<Vec<(usize, usize)>>();
+    turned.sort_by_key(|(x, y)| (*y, *x));
+    assert_eq!(dark_pixels("image_upside_down"), turned);
+}
+
+#[test]
+fn it_prints_with_the_second_ink() {
+    let text = [&[0x1B, b'@', 0x1B, b'r', 1][..], b"Red\n"].concat();
+
+    let html = render_html("second_ink", Context::new(), &text);
+    assert!(html.contains("c2") && html.contains("--ink2"));
+
+    render_image("second_ink", Context::new(), &text);
+    let (frame, pixels) = read_png_frame("second_ink");
+    assert_eq!(frame.color_type, png::ColorType::Rgb);
+
+    //The text is all red, nothing is printed in black
+    assert!(pixels
+        .chunks(3)
+        .any(|rgb| rgb[0] > 128 && rgb[1] < 128 && rgb[2] < 128));
+    assert!(!pixels
+        .chunks(3)
+        .any(|rgb| rgb[0] < 128 && rgb[1] < 128 && rgb[2] < 128));
+}
+
+#[test]
+fn it_smooths_enlarged_characters() {
+    let enlarged = [0x1B, b'@', 0x1D, b'!', 0x11];
+    let smoothing = [0x1D, b'b', 1];
+    render_image(
+        "unsmoothed",
+        Context::new(),
+        &[&enlarged[..], b"AB\n"].concat(),
+    );
+    let smoothed = [&enlarged[..], &smoothing, b"AB\n"].concat();
+    render_image("smoothed", Context::new(), &smoothed);
+
+    let unsmoothed = read_png("unsmoothed");
+    let smoothed = read_png("smoothed");
+    assert_eq!(unsmoothed.len(), smoothed.len());
+    assert!(unsmoothed != smoothed);
+}
+
 fn it_renders(filename: &str) {
     it_renders_image(filename);
     it_renders_html(filename);
@@ -96,6 +406,138 @@ fn it_renders_image(filename: &str) {
     command_parser.parse_bytes(&bytes);
 }
 
+//Parses into a shared context that replies to a response buffer, so tests
+//can check what was sent back and change the printer state between jobs
+fn response_parser(
+    mut renderer: impl CommandRenderer + 'static,
+    mut context: Context,
+) -> (Parser, Rc<RefCell<Context>>, Arc<ResponseBuffer>) {
+    let responses = Arc::new(ResponseBuffer::new());
+    context.response_sink = Some(responses.clone());
+    let context = Rc::new(RefCell::new(context));
+
+    let rendered = context.clone();
+    let on_new_command = move |cmd: Command| {
+        renderer.process_command(&mut rendered.borrow_mut(), &cmd);
+    };
+    let command_parser = thermal_parser::new_esc_pos_parser(Box::from(on_new_command));
+    (command_parser, context, responses)
+}
+
+//Each printed page uses up its height in paper, once per print
+fn assert_page_paper(renderer: impl CommandRenderer + 'static) {
+    let mut context = Context::new();
+    context.printer_state.paper_roll_rows = Some(100_000);
+    let (mut command_parser, context, _) = response_parser(renderer, context);
+
+    let page = [&[0x1B, b'L'][..], b"page\n", &[0x0C, 0x1B, b'@']].concat();
+    command_parser.parse_bytes(&page);
+    let page_rows = context.borrow().printer_state.paper_rows_used;
+    assert!(page_rows > 0);
+
+    //ESC FF prints the page and keeps it, FF prints it again
+    let reprinted = [
+        &[0x1B, b'L'][..],
+        b"page\n",
+        &[0x1B, 0x0C, 0x0C, 0x1B, b'@'],
+    ]
+    .concat();
+    command_parser.parse_bytes(&reprinted);
+    assert_eq!(
+        context.borrow().printer_state.paper_rows_used,
+        page_rows * 3
+    );
+}
+
+//Renders the bytes to an image, returning each command's name and the y position after it
+fn render_positions(name: &str, bytes: &[u8]) -> Vec<(String, usize)> {
+    let positions = Rc::new(RefCell::new(vec![]));
+    let collected = positions.clone();
+    let mut image_renderer = ImageRenderer::new(get_out_path(name));
+    let mut context = Context::new();
+
+    let on_new_command = move |cmd: Command| {
+        image_renderer.process_command(&mut context, &cmd);
+        let name = cmd.name.to_string();
+        collected.borrow_mut().push((name, context.graphics.y));
+    };
+
+    let mut command_parser = thermal_parser::new_esc_pos_parser(Box::from(on_new_command));
+    command_parser.parse_bytes(&bytes.to_vec());
+    positions.take()
+}
+
+fn y_after(positions: &[(String, usize)], command_name: &str) -> Vec<usize> {
+    positions
+        .iter()
+        .filter(|(name, _)| name == command_name)
+        .map(|(_, y)| *y)
+        .collect()
+}
+
+//Renders the bytes to html starting from the given context, returning the html
+fn render_html(name: &str, mut context: Context, bytes: &[u8]) -> String {
+    let mut html_renderer = HtmlRenderer::new(get_out_path(name));
+
+    let on_new_command = move |cmd: Command| {
+        html_renderer.process_command(&mut context, &cmd);
+    };
+
+    let mut command_parser = thermal_parser::new_esc_pos_parser(Box::from(on_new_command));
+    command_parser.parse_bytes(&bytes.to_vec());
+    std::fs::read_to_string(format!("{}.html", get_out_path(name))).unwrap()
+}
+
+fn render_image(name: &str, mut context: Context, bytes: &[u8]) {
+    let mut image_renderer = ImageRenderer::new(get_out_path(name));
+
+    let on_new_command = move |cmd: Command| {
+        image_renderer.process_command(&mut context, &cmd);
+    };
+
+    let mut command_parser = thermal_parser::new_esc_pos_parser(Box::from(on_new_command));
+    command_parser.parse_bytes(&bytes.to_vec());
+}
+
+//Nothing but white paper
+fn is_blank(pixels: &[u8]) -> bool {
+    pixels.iter().all(|pixel| *pixel == 255)
+}
+
+//Pixels of an image rendered to the out folder
+fn read_png(name: &str) -> Vec<u8> {
+    read_png_frame(name).1
+}
+
+fn read_png_frame(name: &str) -> (png::OutputInfo, Vec<u8>) {
+    let file = std::fs::File::open(format!("{}.png", get_out_path(name))).unwrap();
+    let mut reader = png::Decoder::new(file).read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut pixels).unwrap();
+    pixels.truncate(frame.buffer_size());
+    (frame, pixels)
+}
+
+//Positions of the dark pixels of a grayscale image
+fn dark_pixels(name: &str) -> Vec<(usize, usize)> {
+    let (frame, pixels) = read_png_frame(name);
+    let width = frame.width as usize;
+    (0..pixels.len())
+        .filter(|i| pixels[*i] < 128)
+        .map(|i| (i % width, i / width))
+        .collect()
+}
+
+fn get_out_path(name: &str) -> String {
+    format!(
+        "{}/{}/{}/{}",
+        env!("CARGO_MANIFEST_DIR"),
+        "resources",
+        "out",
+        name
+    )
+}
+
 fn get_test_bin(name: &str) -> PathBuf {
     PathBuf::from(env!("CARGO_MANIFEST_DIR"))
         .join("resources")